@@ -0,0 +1,173 @@
+use crate::header_builder::{build_import_crate, MOCKTOPUS_CRATE_NAME};
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::{parse_quote, ArgCaptured, FnArg, Item, ItemFn, Pat, PatIdent, ReturnType, Type, Visibility};
+
+const COMPANION_NAME_PREFIX:    &str = "__mock_";
+const ARG_NAME_PREFIX:          &str = "__mocktopus_arg_";
+
+/// Builds companion of mockable function with typed mock helpers, e.g. for `fn greet(name: &str) -> String`:
+///
+/// ```
+/// pub struct __mock_greet<A0> { pub name: A0 }
+///
+/// impl __mock_greet<()> {
+///     pub fn returns<A0, V>(value: V) where V: Clone + 'static, fn(&str) -> String: FnOnce(A0) -> V { .. }
+///     pub fn calls<M>(mock: M) where M: FnMut(__mock_greet<&str>) -> String + 'static { .. }
+///     pub fn expect<M>(times: usize, mock: M) where M: FnMut(__mock_greet<&str>) -> String + 'static { .. }
+/// }
+/// ```
+///
+/// Argument types are spelled only inside of `FnMut` and `fn` pointer types, where lifetime elision works like
+/// in the function, so the compiler checks that returned values have the function return type.
+/// Companion items get `#[cfg]` and `#[cfg_attr]` attributes of the function, so they exist only together with it.
+/// Returns no items if function signature can't be spelled this way.
+pub fn build_companion(item_fn: &ItemFn, crate_path: Option<&str>) -> Vec<Item> {
+    if !is_companion_supported(item_fn) {
+        return vec![]
+    }
+    let ItemFn { ref attrs, ref ident, ref decl, .. } = *item_fn;
+    let vis = &cap_visibility(&item_fn.vis);
+    let mut field_names = vec![];
+    let mut arg_types = vec![];
+    for (i, fn_arg) in decl.inputs.iter().enumerate() {
+        match *fn_arg {
+            FnArg::Captured(ArgCaptured { ref pat, ref ty, .. }) => {
+                field_names.push(field_name(pat, i));
+                arg_types.push(ty);
+            },
+            _ => return vec![],
+        }
+    }
+    if has_duplicates(&field_names) {
+        return vec![]
+    }
+    let output = match decl.output {
+        ReturnType::Default => quote!(()),
+        ReturnType::Type(_, ref ty) => ty.into_token_stream(),
+    };
+    let companion_ident = Ident::new(&format!("{}{}", COMPANION_NAME_PREFIX, ident), ident.span());
+    let type_params: Vec<_> = (0..field_names.len())
+        .map(|i| Ident::new(&format!("A{}", i), Span::call_site()))
+        .collect();
+    let unit_params: Vec<_> = type_params.iter().map(|_| quote!(())).collect();
+    let arg_idents: Vec<_> = (0..field_names.len())
+        .map(|i| Ident::new(&format!("{}{}__", ARG_NAME_PREFIX, i), Span::call_site()))
+        .collect();
+    let crate_name = Ident::new(MOCKTOPUS_CRATE_NAME, Span::call_site());
+    let import_crate = build_import_crate(crate_path);
+    let fields: Vec<_> = field_names.iter().zip(&type_params).map(|(name, param)| quote!(pub #name: #param)).collect();
+    let field_inits: Vec<_> = field_names.iter().zip(&arg_idents).map(|(name, arg)| quote!(#name: #arg)).collect();
+    let cfg_attrs: Vec<_> = attrs.iter()
+        .filter(|attr| attr.path.is_ident("cfg") || attr.path.is_ident("cfg_attr"))
+        .collect();
+    let cfg_attrs = quote!(#(#cfg_attrs)*);
+    let arg_types = quote!(#(#arg_types),*);
+    let type_params = quote!(#(#type_params,)*);
+    let mock_bound = quote!(M: FnMut(#companion_ident<#arg_types>) -> #output + 'static);
+    let returns_bound = quote!(fn(#arg_types) -> #output: FnOnce(#type_params) -> V);
+    let mock_closure = quote! {
+        move |(#(#arg_idents,)*)| #crate_name::mocking::MockResult::Return(
+            mock(#companion_ident { #(#field_inits),* }))
+    };
+    let companion_struct = parse_quote! {
+        #cfg_attrs
+        #[doc(hidden)]
+        #[allow(non_camel_case_types, dead_code)]
+        #vis struct #companion_ident<#type_params> {
+            #(#fields),*
+        }
+    };
+    let companion_impl = parse_quote! {
+        #cfg_attrs
+        #[allow(dead_code)]
+        impl #companion_ident<#(#unit_params),*> {
+            /// Mocks the function to return clone of the value on every call
+            #vis fn returns<#type_params V>(value: V) where V: Clone + 'static, #returns_bound {
+                #import_crate
+                #crate_name::mocking::mock_returns(&#ident, value)
+            }
+
+            /// Mocks the function to return result of the closure receiving named arguments
+            #vis fn calls<M>(mut mock: M) where #mock_bound {
+                #import_crate
+                #crate_name::mocking::mock_tuple(&#ident, #mock_closure)
+            }
+
+            /// Like [calls](#method.calls), but also expects the function to be called given number of times
+            #vis fn expect<M>(times: usize, mut mock: M) where #mock_bound {
+                #import_crate
+                #crate_name::mocking::mock_tuple_expect(&#ident, times, #mock_closure)
+            }
+        }
+    };
+    vec![companion_struct, companion_impl]
+}
+
+/// Companions are test helpers, so they aren't visible outside of the crate even if the function is
+fn cap_visibility(vis: &Visibility) -> Visibility {
+    match *vis {
+        Visibility::Public(_) => parse_quote!(pub(crate)),
+        _ => vis.clone(),
+    }
+}
+
+fn is_companion_supported(item_fn: &ItemFn) -> bool {
+    let decl = &item_fn.decl;
+    if item_fn.asyncness.is_some() || !decl.generics.params.is_empty() || decl.generics.where_clause.is_some() {
+        return false
+    }
+    let arg_types = decl.inputs.iter()
+        .filter_map(|fn_arg| match *fn_arg {
+            FnArg::Captured(ArgCaptured { ref ty, .. }) => Some(ty),
+            _ => None,
+        });
+    let output_type = match decl.output {
+        ReturnType::Default => None,
+        ReturnType::Type(_, ref ty) => Some(&**ty),
+    };
+    arg_types.chain(output_type).all(is_type_nameable)
+}
+
+/// Checks if type can be spelled outside of function signature:
+/// it can't contain `impl Trait`, `Self`, `!`, macros or named lifetimes other than `'static`
+fn is_type_nameable(ty: &Type) -> bool {
+    is_token_stream_nameable(ty.into_token_stream())
+}
+
+fn is_token_stream_nameable(token_stream: TokenStream) -> bool {
+    let mut tokens = token_stream.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Group(group) => if !is_token_stream_nameable(group.stream()) {
+                return false
+            },
+            TokenTree::Ident(ref ident) => if ident == "impl" || ident == "Self" {
+                return false
+            },
+            TokenTree::Punct(ref punct) => match punct.as_char() {
+                '!' => return false,
+                '\'' => match tokens.peek() {
+                    Some(TokenTree::Ident(ident)) if ident == "static" => (),
+                    _ => return false,
+                },
+                _ => (),
+            },
+            TokenTree::Literal(_) => (),
+        }
+    }
+    true
+}
+
+fn field_name(pat: &Pat, index: usize) -> Ident {
+    match *pat {
+        Pat::Ident(PatIdent { ref ident, .. }) if !ident.to_string().starts_with("__mocktopus_") => ident.clone(),
+        _ => Ident::new(&format!("arg{}", index), Span::call_site()),
+    }
+}
+
+fn has_duplicates(names: &[Ident]) -> bool {
+    names.iter()
+        .enumerate()
+        .any(|(i, name)| names[..i].contains(name))
+}
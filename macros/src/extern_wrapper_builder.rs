@@ -0,0 +1,125 @@
+use crate::injector_config::InjectorConfig;
+use crate::item_injector;
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{braced, parse_quote};
+use syn::{ArgCaptured, Attribute, FnArg, Ident, Item, MethodSig, Pat, PatIdent, Path, Token, TraitItemMethod,
+          Visibility};
+use syn::parse::{Parse, ParseStream};
+use syn::token::Brace;
+
+/// Bodiless function declaration, which is parsed like a trait method
+pub struct WrappedFn {
+    attrs:  Vec<Attribute>,
+    vis:    Visibility,
+    sig:    MethodSig,
+}
+
+impl Parse for WrappedFn {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut attrs = input.call(Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        let method: TraitItemMethod = input.parse()?;
+        if method.default.is_some() {
+            return Err(input.error("wrapped function must not have body"))
+        }
+        attrs.extend(method.attrs);
+        Ok(WrappedFn { attrs, vis, sig: method.sig })
+    }
+}
+
+/// `mod name = path::to::module { wrapped functions }`
+struct WrappedMod {
+    attrs:  Vec<Attribute>,
+    vis:    Visibility,
+    ident:  Ident,
+    path:   Path,
+    fns:    Vec<WrappedFn>,
+}
+
+impl Parse for WrappedMod {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        input.parse::<Token![mod]>()?;
+        let ident = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let path = input.call(Path::parse_mod_style)?;
+        let content;
+        let _: Brace = braced!(content in input);
+        let mut fns = vec![];
+        while !content.is_empty() {
+            fns.push(content.parse()?);
+        }
+        Ok(WrappedMod { attrs, vis, ident, path, fns })
+    }
+}
+
+/// Content of `mock_extern!` invocation
+pub struct WrappedMods(Vec<WrappedMod>);
+
+impl Parse for WrappedMods {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut mods = vec![];
+        while !input.is_empty() {
+            mods.push(input.parse()?);
+        }
+        Ok(WrappedMods(mods))
+    }
+}
+
+pub fn build_wrapped_mods(wrapped_mods: &WrappedMods) -> Result<TokenStream, String> {
+    let mut output = TokenStream::new();
+    for wrapped_mod in &wrapped_mods.0 {
+        let WrappedMod { ref attrs, ref vis, ref ident, ref path, ref fns } = *wrapped_mod;
+        let mut wrappers = vec![];
+        for wrapped_fn in fns {
+            let fn_ident = &wrapped_fn.sig.ident;
+            let fn_path = parse_quote!(#path::#fn_ident);
+            wrappers.push(build_wrapper_fn(wrapped_fn, &parse_quote!(pub), &fn_path)?);
+        }
+        output.extend(quote! {
+            #(#attrs)*
+            #vis mod #ident {
+                #[allow(unused_imports)]
+                pub use #path::*;
+                #[allow(unused_imports)]
+                use super::*;
+
+                #(#wrappers)*
+            }
+        });
+    }
+    Ok(output)
+}
+
+/// Wrapper has given visibility, which overrides declared one
+fn build_wrapper_fn(wrapped_fn: &WrappedFn, vis: &Visibility, wrapped_path: &Path) -> Result<Item, String> {
+    let WrappedFn { ref attrs, ref sig, .. } = *wrapped_fn;
+    if sig.constness.is_some() || sig.unsafety.is_some() || sig.asyncness.is_some() || sig.decl.variadic.is_some() {
+        return Err(format!("function '{}' can't be wrapped, const, unsafe, async and variadic functions \
+            can't be made mockable", sig.ident))
+    }
+    let mut arg_idents = vec![];
+    for fn_arg in &sig.decl.inputs {
+        match *fn_arg {
+            FnArg::Captured(ArgCaptured { pat: Pat::Ident(PatIdent { ref ident, subpat: None, .. }), .. }) =>
+                arg_idents.push(ident),
+            _ => return Err(format!("argument '{}' of function '{}' can't be wrapped, expected `name: Type`",
+                                    fn_arg.clone().into_token_stream(), sig.ident)),
+        }
+    }
+    let mut wrapper: Item = parse_quote! {
+        #(#attrs)*
+        #vis #sig {
+            #wrapped_path(#(#arg_idents),*)
+        }
+    };
+    item_injector::inject_item(&mut wrapper, &InjectorConfig::default());
+    Ok(wrapper)
+}
+
+/// Wrapper has declared visibility
+pub fn build_declared_wrapper_fn(wrapped_fn: &WrappedFn, wrapped_path: &Path) -> Result<Item, String> {
+    build_wrapper_fn(wrapped_fn, &wrapped_fn.vis, wrapped_path)
+}
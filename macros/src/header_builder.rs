@@ -1,16 +1,22 @@
 use crate::display_delegate::display;
-use proc_macro2::{Group, Span, TokenTree};
+use proc_macro2::{Group, Span, TokenStream, TokenTree};
 use quote::{ToTokens};
 use std::fmt::{Error, Formatter};
 use syn::{self, ArgCaptured, Block, Expr, ExprVerbatim, FnArg, FnDecl, GenericParam, Ident, Pat, PatIdent, PathSegment,
-          Stmt};
+          ReturnType, Stmt, Type};
 use syn::punctuated::Punctuated;
 use syn::token::{Colon2, Semi};
 
-const MOCKTOPUS_CRATE_NAME:     &str = "__mocktopus_crate__";
+pub const MOCKTOPUS_CRATE_NAME: &str = "__mocktopus_crate__";
 const STD_CRATE_NAME:           &str = "__mocktopus_std__";
 const ARGS_TO_CONTINUE_NAME:    &str = "__mocktopus_args_to_continue__";
+const MOCK_RESULT_NAME:         &str = "__mocktopus_mock_result__";
 const UNWIND_DATA_NAME:         &str = "__mocktopus_unwind_data__";
+const RECEIVER_NAME:            &str = "__mocktopus_receiver__";
+const TRAIT_ARG_NAME_PREFIX:    &str = "__mocktopus_trait_arg_";
+/// Maximum number of arguments without receiver of trait methods mockable for all implementors,
+/// `mocktopus::mocking::TraitMethodArgs` is implemented only for tuples up to this length
+const MAX_TRAIT_MOCK_ARGS:      usize = 12;
 
 macro_rules! error_msg {
     ($msg:expr) => { concat!("Mocktopus internal error: ", $msg) }
@@ -19,25 +25,32 @@ macro_rules! error_msg {
 pub enum FnHeaderBuilder<'a> {
     StaticFn,
     StructImpl,
-    TraitDefault,
-    TraitImpl(&'a Punctuated<PathSegment, Colon2>),
+    TraitDefault(&'a Ident),
+    /// Path of implemented trait and whether implementing type is known to be `'static`
+    TraitImpl(&'a Punctuated<PathSegment, Colon2>, bool),
+    DropImpl,
 }
 
 impl<'a> FnHeaderBuilder<'a> {
-    pub fn build(&self, fn_ident: &Ident, fn_decl: &FnDecl, fn_block_span: Span) -> Stmt {
+    /// Registers function as mockable if it can be referenced outside of its body with `registered_path`
+    pub fn build(&self, fn_ident: &Ident, fn_decl: &FnDecl, fn_block_span: Span, crate_path: Option<&str>,
+                 registered_path: Option<&str>) -> Stmt {
+        if let FnHeaderBuilder::DropImpl = *self {
+            return build_drop_header(fn_block_span, crate_path)
+        }
         let fn_args = &fn_decl.inputs;
         let header_str = format!(
 r#"{{
-    extern crate mocktopus as {mocktopus};
+    {import_crate}
     extern crate std as {std_crate};
+    {register}
     match {std_crate}::panic::catch_unwind({std_crate}::panic::AssertUnwindSafe (
-            || {mocktopus}::mocking::Mockable::call_mock(&{full_fn_name}, {extract_args}))) {{
+            || {call_mock})) {{
         Ok({mocktopus}::mocking::MockResult::Continue({args_to_continue})) => {restore_args},
-        Ok({mocktopus}::mocking::MockResult::Return(result)) => {{
+        Ok({mocktopus}::mocking::MockResult::Return(result)) => {return_result},
+        Ok({mock_result}) => {{
             {forget_args}
-            let returned = unsafe {{ {std_crate}::mem::transmute_copy(&result) }};
-            {std_crate}::mem::forget(result);
-            return returned;
+            {mock_result}.resume_panic();
         }},
         Err({unwind}) => {{
             {forget_args}
@@ -45,13 +58,16 @@ r#"{{
         }},
     }}
 }}"#,
+        import_crate        = display(|f| write_import_crate(f, crate_path)),
         mocktopus           = MOCKTOPUS_CRATE_NAME,
         std_crate           = STD_CRATE_NAME,
-        full_fn_name        = display(|f| write_full_fn_name(f, self, fn_ident, fn_decl)),
-        extract_args        = display(|f| write_extract_args(f, fn_args)),
+        register            = display(|f| write_register(f, registered_path)),
+        call_mock           = display(|f| write_call_mock(f, self, fn_ident, fn_decl)),
         args_to_continue    = ARGS_TO_CONTINUE_NAME,
+        return_result       = display(|f| write_return_result(f, fn_decl)),
         restore_args        = display(|f| write_restore_args(f, fn_args)),
         forget_args         = display(|f| write_forget_args(f, fn_args)),
+        mock_result         = MOCK_RESULT_NAME,
         unwind              = UNWIND_DATA_NAME);
         let header_block = syn::parse_str::<Block>(&header_str)
             .expect(error_msg!("generated header unparsable"));
@@ -59,9 +75,67 @@ r#"{{
     }
 }
 
+fn build_drop_header(fn_block_span: Span, crate_path: Option<&str>) -> Stmt {
+    let header_str = format!(
+r#"{{
+    {import_crate}
+    extern crate std as {std_crate};
+    match {mocktopus}::mocking::call_drop_mock(self) {{
+        {mocktopus}::mocking::MockResult::Continue(()) => (),
+        {mocktopus}::mocking::MockResult::Return(()) => return,
+        mock_result => mock_result.resume_panic(),
+    }}
+}}"#,
+        import_crate    = display(|f| write_import_crate(f, crate_path)),
+        mocktopus       = MOCKTOPUS_CRATE_NAME,
+        std_crate       = STD_CRATE_NAME);
+    let header_block = syn::parse_str::<Block>(&header_str)
+        .expect(error_msg!("generated drop header unparsable"));
+    create_call_site_spanned_stmt(header_block, fn_block_span)
+}
+
+fn write_register(f: &mut Formatter, registered_path: Option<&str>) -> Result<(), Error> {
+    match registered_path {
+        Some(registered_path)   => write!(f, "{}::__register_mockable!({});", MOCKTOPUS_CRATE_NAME, registered_path),
+        None                    => Ok(()),
+    }
+}
+
+fn write_return_result(f: &mut Formatter, fn_decl: &FnDecl) -> Result<(), Error> {
+    if returns_never(fn_decl) {
+        return write!(f, "match result {{}}")
+    }
+    write!(f,
+r#"{{
+    {forget_args}
+    let returned = unsafe {{ {std_crate}::mem::transmute_copy(&result) }};
+    {std_crate}::mem::forget(result);
+    return returned;
+}}"#,
+        forget_args = display(|f| write_forget_args(f, &fn_decl.inputs)),
+        std_crate   = STD_CRATE_NAME)
+}
+
+/// Builds statement importing Mocktopus crate as `__mocktopus_crate__`
+pub fn build_import_crate(crate_path: Option<&str>) -> TokenStream {
+    display(|f| write_import_crate(f, crate_path)).to_string()
+        .parse()
+        .expect(error_msg!("generated crate import unparsable"))
+}
+
+fn write_import_crate(f: &mut Formatter, crate_path: Option<&str>) -> Result<(), Error> {
+    match crate_path {
+        Some(crate_path)    => write!(f, "use {} as {};", crate_path, MOCKTOPUS_CRATE_NAME),
+        None                => write!(f, "extern crate mocktopus as {};", MOCKTOPUS_CRATE_NAME),
+    }
+}
+
+/// Creates statement from header block, clippy lints are allowed, because they would point at the annotated function
 fn create_call_site_spanned_stmt(block: Block, span: Span) -> Stmt {
-    let token_stream = block.into_token_stream()
-        .into_iter()
+    let allow_clippy: TokenStream = "#[allow(clippy::all)]".parse()
+        .expect(error_msg!("generated lint attribute unparsable"));
+    let token_stream = allow_clippy.into_iter()
+        .chain(block.into_token_stream())
         .map(|tt| make_token_tree_span_call_site(tt, span))
         .collect();
     Stmt::Semi(
@@ -85,13 +159,112 @@ fn make_token_tree_span_call_site(mut token_tree: TokenTree, span: Span) -> Toke
     token_tree
 }
 
+fn write_call_mock(f: &mut Formatter, builder: &FnHeaderBuilder, fn_ident: &Ident, fn_decl: &FnDecl)
+        -> Result<(), Error> {
+    let trait_ident = match *builder {
+        FnHeaderBuilder::StaticFn |
+        FnHeaderBuilder::StructImpl |
+        FnHeaderBuilder::DropImpl               => None,
+        FnHeaderBuilder::TraitDefault(ident)    => Some(ident),
+        FnHeaderBuilder::TraitImpl(path, _)     => path.last().map(|pair| &pair.into_value().ident),
+    };
+    let fn_args = &fn_decl.inputs;
+    let trait_ident = trait_ident
+        .filter(|_| fn_args.iter().filter(|fn_arg| !is_receiver(fn_arg)).count() <= MAX_TRAIT_MOCK_ARGS);
+    let trait_ident = match trait_ident {
+        Some(trait_ident) => trait_ident,
+        None => {
+            let call_mock_fn = match returns_impl_trait(fn_decl) {
+                true    => "MockableImplTrait::call_impl_trait_mock",
+                false   => "Mockable::call_mock",
+            };
+            return write!(f, "{}::mocking::{}(&{}, {})", MOCKTOPUS_CRATE_NAME, call_mock_fn,
+                          display(|f| write_full_fn_name(f, builder, fn_ident, fn_decl)),
+                          display(|f| write_extract_args(f, fn_args)))
+        },
+    };
+    write!(f,
+r#"match {mocktopus}::mocking::Mockable::call_mock(&{full_fn_name}, {extract_args}) {{
+    {mocktopus}::mocking::MockResult::Continue(({all_args})) => {{
+        let __mocktopus_implementor__ = {mocktopus}::mocking::TraitImplementor::{new_implementor};
+        match {mocktopus}::mocking::call_trait_mock("{trait_name}", "{fn_name}", __mocktopus_implementor__, ({trait_args})) {{
+            {mocktopus}::mocking::MockResult::Continue(({trait_args})) =>
+                {mocktopus}::mocking::MockResult::Continue(({all_args})),
+            {mocktopus}::mocking::MockResult::Return(result) => {mocktopus}::mocking::MockResult::Return(result),
+            mock_result => mock_result.into_failure(),
+        }}
+    }},
+    mock_result => mock_result,
+}}"#,
+        mocktopus       = MOCKTOPUS_CRATE_NAME,
+        full_fn_name    = display(|f| write_full_fn_name(f, builder, fn_ident, fn_decl)),
+        extract_args    = display(|f| write_extract_args(f, fn_args)),
+        all_args        = display(|f| write_trait_arg_names(f, fn_args, true)),
+        new_implementor = display(|f| write_new_implementor(f, builder, fn_args)),
+        trait_name      = trait_ident,
+        fn_name         = fn_ident,
+        trait_args      = display(|f| write_trait_arg_names(f, fn_args, false)))
+}
+
+fn returns_impl_trait(fn_decl: &FnDecl) -> bool {
+    match fn_decl.output {
+        ReturnType::Type(_, ref ty) => matches!(**ty, Type::ImplTrait(_)),
+        ReturnType::Default => false,
+    }
+}
+
+/// Functions returning `!` can't be mocked to return
+fn returns_never(fn_decl: &FnDecl) -> bool {
+    match fn_decl.output {
+        ReturnType::Type(_, ref ty) => matches!(**ty, Type::Never(_)),
+        ReturnType::Default => false,
+    }
+}
+
+fn write_trait_arg_names<T>(f: &mut Formatter, fn_args: &Punctuated<FnArg, T>, with_receiver: bool)
+        -> Result<(), Error> {
+    for (fn_arg_index, fn_arg) in fn_args.iter().enumerate() {
+        if !is_receiver(fn_arg) {
+            write!(f, "{}{}__, ", TRAIT_ARG_NAME_PREFIX, fn_arg_index)?;
+        } else if with_receiver {
+            write!(f, "{}, ", RECEIVER_NAME)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_new_implementor<T>(f: &mut Formatter, builder: &FnHeaderBuilder, fn_args: &Punctuated<FnArg, T>)
+        -> Result<(), Error> {
+    match *builder {
+        FnHeaderBuilder::TraitImpl(_, true) => write!(f, "new::<Self>({})", display(|f| write_receiver_ref(f, fn_args))),
+        _                                   => write!(f, "without_receiver::<Self>()"),
+    }
+}
+
+fn write_receiver_ref<T>(f: &mut Formatter, fn_args: &Punctuated<FnArg, T>) -> Result<(), Error> {
+    match fn_args.first().map(|pair| pair.into_value()) {
+        Some(&FnArg::SelfValue(_))                  => write!(f, "Some(&{})", RECEIVER_NAME),
+        Some(fn_arg) if is_receiver(fn_arg)         => write!(f, "Some(&*{})", RECEIVER_NAME),
+        _                                           => write!(f, "None"),
+    }
+}
+
+fn is_receiver(fn_arg: &FnArg) -> bool {
+    match *fn_arg {
+        FnArg::SelfRef(_) | FnArg::SelfValue(_) => true,
+        FnArg::Captured(ArgCaptured { pat: Pat::Ident(PatIdent { ref ident, .. }), .. }) => ident == "self",
+        _ => false,
+    }
+}
+
 fn write_full_fn_name(f: &mut Formatter, builder: &FnHeaderBuilder, fn_ident: &Ident, fn_decl: &FnDecl)
         -> Result<(), Error> {
     match *builder {
-        FnHeaderBuilder::StaticFn               => (),
+        FnHeaderBuilder::StaticFn |
+        FnHeaderBuilder::DropImpl               => (),
         FnHeaderBuilder::StructImpl |
-        FnHeaderBuilder::TraitDefault           => write!(f, "Self::")?,
-        FnHeaderBuilder::TraitImpl(ref path)    => write!(f, "<Self as {}>::", display(|f| write_trait_path(f, path)))?,
+        FnHeaderBuilder::TraitDefault(_)        => write!(f, "Self::")?,
+        FnHeaderBuilder::TraitImpl(path, _)     => write!(f, "<Self as {}>::", display(|f| write_trait_path(f, path)))?,
     }
     write!(f, "{}::<{}>", fn_ident, display(|f| write_fn_generics(f, fn_decl)))
 }
@@ -103,29 +276,47 @@ fn write_trait_path<T: ToTokens + Clone>(f: &mut Formatter, path: &Punctuated<Pa
 fn write_fn_generics(f: &mut Formatter, fn_decl: &FnDecl) -> Result<(), Error> {
     fn_decl.generics.params.iter()
         .filter_map(get_generic_param_name)
-        .map(|param| write!(f, "{},", param))
-        .collect()
+        .try_for_each(|param| write!(f, "{},", param))
 }
 
 fn get_generic_param_name(param: &GenericParam) -> Option<String> {
     match *param {
-        GenericParam::Type(ref type_param) => Some(type_param.ident.to_string()),
-        _ => None,
+        GenericParam::Type(ref type_param)      => Some(type_param.ident.to_string()),
+        GenericParam::Const(ref const_param)    => Some(const_param.ident.to_string()),
+        GenericParam::Lifetime(_)               => None,
     }
 }
 
+/// Arguments are cast, so their lifetimes aren't tied to the function's ones. Types of `impl Trait` arguments can't be
+/// named, so they are passed as they are and determine the mocked function's generic parameters.
 fn write_extract_args<T>(f: &mut Formatter, fn_args: &Punctuated<FnArg, T>) -> Result<(), Error> {
     if fn_args.is_empty() {
         return write!(f, "()");
     }
     write!(f, "unsafe {{ (")?;
-    for fn_arg_name in iter_fn_arg_names(fn_args) {
-        write!(f, "{}::mem::replace(&mut *(&{} as *const _ as *mut _), {}::mem::uninitialized()), ",
-               STD_CRATE_NAME, fn_arg_name, STD_CRATE_NAME)?;
+    for (fn_arg, fn_arg_name) in fn_args.iter().zip(iter_fn_arg_names(fn_args)) {
+        match *fn_arg {
+            FnArg::Captured(ArgCaptured { ref ty, .. }) if contains_impl_trait(ty) =>
+                write!(f, "{}::ptr::read(&{}), ", STD_CRATE_NAME, fn_arg_name)?,
+            _ => write!(f, "{}::ptr::read(&{} as *const _ as *const _), ", STD_CRATE_NAME, fn_arg_name)?,
+        }
     }
     write!(f, ") }}")
 }
 
+pub fn contains_impl_trait(ty: &Type) -> bool {
+    contains_impl_keyword(ty.into_token_stream())
+}
+
+fn contains_impl_keyword(token_stream: TokenStream) -> bool {
+    token_stream.into_iter()
+        .any(|token| match token {
+            TokenTree::Group(group) => contains_impl_keyword(group.stream()),
+            TokenTree::Ident(ident) => ident == "impl",
+            _ => false,
+        })
+}
+
 fn write_restore_args<T>(f: &mut Formatter, fn_args: &Punctuated<FnArg, T>) -> Result<(), Error> {
     if fn_args.is_empty() {
         return writeln!(f, "()");
@@ -0,0 +1,126 @@
+use syn::{Ident, Lit, Meta, MetaList, MetaNameValue, NestedMeta, Path};
+use syn::punctuated::Punctuated;
+use syn::token::Comma;
+
+#[derive(Default)]
+pub struct InjectorConfig {
+    only:       Option<Vec<String>>,
+    except:     Vec<String>,
+    pub_only:   bool,
+    name:       Option<String>,
+    unlinked:   bool,
+    safe:       bool,
+    crate_path: Option<String>,
+}
+
+impl InjectorConfig {
+    pub fn parse(args: &Punctuated<NestedMeta, Comma>) -> Result<Self, String> {
+        let mut config = InjectorConfig::default();
+        for arg in args {
+            match *arg {
+                NestedMeta::Meta(Meta::Word(ref ident)) if ident == "pub_only" => config.pub_only = true,
+                NestedMeta::Meta(Meta::Word(ref ident)) if ident == "unlinked" => config.unlinked = true,
+                NestedMeta::Meta(Meta::Word(ref ident)) if ident == "safe" => config.safe = true,
+                NestedMeta::Meta(Meta::List(ref list)) if list.ident == "only" =>
+                    config.only.get_or_insert_with(Vec::new).extend(parse_fn_names(list)?),
+                NestedMeta::Meta(Meta::List(ref list)) if list.ident == "except" =>
+                    config.except.extend(parse_fn_names(list)?),
+                NestedMeta::Meta(Meta::NameValue(ref name_value)) if name_value.ident == "name" =>
+                    config.name = Some(parse_str_value(name_value)?),
+                NestedMeta::Meta(Meta::NameValue(ref name_value)) if name_value.ident == "mocktopus" =>
+                    config.crate_path = Some(parse_path_value(name_value)?),
+                _ => return Err(format!("unknown argument '{}', expected one of: only(..), except(..), pub_only, \
+                    name = \"..\", unlinked, safe, mocktopus = \"..\"", display_tokens(arg))),
+            }
+        }
+        Ok(config)
+    }
+
+    /// Parses arguments of attributes, which accept only `mocktopus = "..."`
+    pub fn parse_crate_path(args: &Punctuated<NestedMeta, Comma>) -> Result<Self, String> {
+        let mut config = InjectorConfig::default();
+        for arg in args {
+            match *arg {
+                NestedMeta::Meta(Meta::NameValue(ref name_value)) if name_value.ident == "mocktopus" =>
+                    config.crate_path = Some(parse_path_value(name_value)?),
+                _ => return Err(format!("unknown argument '{}', expected mocktopus = \"..\"", display_tokens(arg))),
+            }
+        }
+        Ok(config)
+    }
+
+    /// Extern functions should not be linked and their mockable shims should panic unless mocked
+    pub fn is_unlinked(&self) -> bool {
+        self.unlinked
+    }
+
+    /// Extern functions are safe to call with any arguments, so they can get safe mockable shims
+    pub fn is_safe(&self) -> bool {
+        self.safe
+    }
+
+    /// Path to Mocktopus crate used by injected code if it's not available as `extern crate mocktopus`
+    pub fn crate_path(&self) -> Option<&str> {
+        self.crate_path.as_deref()
+    }
+
+    pub fn is_fn_selected(&self, fn_name: &Ident, is_pub: bool) -> bool {
+        let fn_name = fn_name.to_string();
+        if self.pub_only && !is_pub {
+            return false
+        }
+        if let Some(ref only) = self.only {
+            if !only.contains(&fn_name) {
+                return false
+            }
+        }
+        if self.except.contains(&fn_name) {
+            return false
+        }
+        match self.name {
+            Some(ref pattern) => matches_pattern(pattern, &fn_name),
+            None => true,
+        }
+    }
+}
+
+fn parse_fn_names(list: &MetaList) -> Result<Vec<String>, String> {
+    list.nested.iter()
+        .map(|nested| match *nested {
+            NestedMeta::Meta(Meta::Word(ref ident)) => Ok(ident.to_string()),
+            _ => Err(format!("invalid argument '{}' of '{}', expected function name",
+                             display_tokens(nested), list.ident)),
+        })
+        .collect()
+}
+
+fn parse_str_value(name_value: &MetaNameValue) -> Result<String, String> {
+    match name_value.lit {
+        Lit::Str(ref lit_str) => Ok(lit_str.value()),
+        _ => Err(format!("invalid value of '{}', expected string literal", name_value.ident)),
+    }
+}
+
+fn parse_path_value(name_value: &MetaNameValue) -> Result<String, String> {
+    let path = parse_str_value(name_value)?;
+    syn::parse_str::<Path>(&path)
+        .map_err(|_| format!("invalid value of '{}', expected path", name_value.ident))?;
+    Ok(path)
+}
+
+fn display_tokens<T: quote::ToTokens + Clone>(tokens: &T) -> String {
+    tokens.clone().into_token_stream().to_string()
+}
+
+/// Matches name against pattern, in which `*` matches any sequence of characters
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    match pattern.find('*') {
+        None => pattern == name,
+        Some(star_idx) => {
+            let (prefix, rest) = (&pattern[..star_idx], &pattern[star_idx + 1..]);
+            name.starts_with(prefix) && (prefix.len()..=name.len())
+                .filter(|&split| name.is_char_boundary(split))
+                .any(|split| matches_pattern(rest, &name[split..]))
+        }
+    }
+}
@@ -1,37 +1,104 @@
-use crate::header_builder::FnHeaderBuilder;
-use syn::{ArgCaptured, Attribute, Block, FnArg, FnDecl, Ident, ImplItem, ImplItemMethod, Item, ItemFn, ItemImpl,
-          ItemMod, ItemTrait, MethodSig, Pat, PatIdent, TraitItem, TraitItemMethod};
+use crate::companion_builder::build_companion;
+use crate::header_builder::{contains_impl_trait, FnHeaderBuilder};
+use crate::injector_config::InjectorConfig;
+use proc_macro2::{Group, Span, TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::parse_quote;
+use syn::{ArgCaptured, Attribute, Block, FnArg, FnDecl, ForeignItem, ForeignItemFn, GenericParam, Ident, ImplItem,
+          ImplItemMethod, Item, ItemFn, ItemForeignMod, ItemImpl, ItemMod, ItemTrait, MethodSig, Pat, PatIdent,
+          PathSegment, TraitItem, TraitItemMethod, Visibility};
 use syn::punctuated::Punctuated;
-use syn::token::{Comma, Const, Unsafe};
+use syn::token::{Colon2, Comma, Const, Unsafe};
 
-pub fn inject_item(item: &mut Item) {
+/// Parses item, which may contain impls with leading const generic params, e.g. `impl<const N: usize>`.
+/// Syn doesn't recognize them as impl generics, so they're prefixed with an attribute `#[cfg(all())]`,
+/// which is removed when the impl is injected.
+pub fn parse_item(token_stream: TokenStream) -> syn::Result<Item> {
+    syn::parse2(mark_impl_const_generics(token_stream))
+}
+
+fn mark_impl_const_generics(token_stream: TokenStream) -> TokenStream {
+    let mut output = TokenStream::new();
+    let mut tokens = token_stream.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Group(group) => {
+                let mut marked = Group::new(group.delimiter(), mark_impl_const_generics(group.stream()));
+                marked.set_span(group.span());
+                output.extend(Some(TokenTree::Group(marked)));
+            },
+            TokenTree::Ident(ref ident) if ident == "impl" => {
+                output.extend(Some(token.clone()));
+                if is_punct(tokens.peek(), '<') {
+                    output.extend(tokens.next());
+                    if let Some(TokenTree::Ident(ident)) = tokens.peek() {
+                        if ident == "const" {
+                            output.extend(quote!(#[cfg(all())]));
+                        }
+                    }
+                }
+            },
+            _ => output.extend(Some(token)),
+        }
+    }
+    output
+}
+
+fn is_punct(token: Option<&TokenTree>, punct_char: char) -> bool {
+    match token {
+        Some(TokenTree::Punct(punct)) => punct.as_char() == punct_char,
+        _ => false,
+    }
+}
+
+fn is_const_generic_marker(attr: &Attribute) -> bool {
+    quote!(#attr).to_string() == quote!(#[cfg(all())]).to_string()
+}
+
+/// Returns companion items, which should be placed right after the injected item
+pub fn inject_item(item: &mut Item, config: &InjectorConfig) -> Vec<Item> {
     match *item {
-        Item::Fn(ref mut item_fn)       => inject_fn(item_fn),
-        Item::Mod(ref mut item_mod)     => inject_mod(item_mod),
-        Item::Trait(ref mut item_trait) => inject_trait(item_trait),
-        Item::Impl(ref mut item_impl)   => inject_impl(item_impl),
-        _                               => (),
+        Item::Fn(ref mut item_fn)               => return inject_fn(item_fn, config),
+        Item::Mod(ref mut item_mod)             => inject_mod(item_mod, config),
+        Item::Trait(ref mut item_trait)         => inject_trait(item_trait, config),
+        Item::Impl(ref mut item_impl)           => inject_impl(item_impl, config),
+        Item::ForeignMod(ref mut foreign_mod)   => return inject_foreign_mod(foreign_mod, config),
+        _                                       => (),
     }
+    vec![]
 }
 
-fn inject_fn(item_fn: &mut ItemFn) {
-    inject_any_fn(&FnHeaderBuilder::StaticFn, &item_fn.attrs, &item_fn.constness, &item_fn.unsafety, &item_fn.ident,
-                  &mut item_fn.decl, &mut *item_fn.block);
+/// Returns companion items with typed mock helpers if function was injected and its signature allows it
+fn inject_fn(item_fn: &mut ItemFn, config: &InjectorConfig) -> Vec<Item> {
+    if !config.is_fn_selected(&item_fn.ident, is_pub(&item_fn.vis))
+        || !is_fn_injectable(&item_fn.attrs, &item_fn.constness, &item_fn.unsafety, &item_fn.decl) {
+        return vec![]
+    }
+    let companion = build_companion(item_fn, config.crate_path());
+    inject_any_fn(&FnHeaderBuilder::StaticFn, config, Some(""), &item_fn.ident, &mut item_fn.decl, &mut item_fn.block);
+    companion
 }
 
-fn inject_mod(item_mod: &mut ItemMod) {
+fn inject_mod(item_mod: &mut ItemMod, config: &InjectorConfig) {
     if is_not_mockable(&item_mod.attrs) {
         return
     }
-    item_mod.content.iter_mut()
-        .flat_map(|c| &mut c.1)
-        .for_each(inject_item)
+    if let Some((_, ref mut items)) = item_mod.content {
+        let mut injected_items = Vec::with_capacity(items.len());
+        for mut item in items.drain(..) {
+            let companion_items = inject_item(&mut item, config);
+            injected_items.push(item);
+            injected_items.extend(companion_items);
+        }
+        *items = injected_items;
+    }
 }
 
-fn inject_trait(item_trait: &mut ItemTrait) {
+pub fn inject_trait(item_trait: &mut ItemTrait, config: &InjectorConfig) {
     if is_not_mockable(&item_trait.attrs) {
         return
     }
+    let is_trait_pub = is_pub(&item_trait.vis);
     for item in &mut item_trait.items {
         if let TraitItem::Method(TraitItemMethod {
             ref attrs,
@@ -39,54 +106,232 @@ fn inject_trait(item_trait: &mut ItemTrait) {
             default: Some(ref mut block),
             ..
         }) = *item {
-            inject_any_method(&FnHeaderBuilder::TraitDefault, attrs, sig, block);
+            if config.is_fn_selected(&sig.ident, is_trait_pub) {
+                inject_any_method(&FnHeaderBuilder::TraitDefault(&item_trait.ident), config, None, attrs, sig, block);
+            }
         }
     }
 }
 
-fn inject_impl(item_impl: &mut ItemImpl) {
+fn inject_impl(item_impl: &mut ItemImpl, config: &InjectorConfig) {
+    if let Some(&mut GenericParam::Const(ref mut const_param)) = item_impl.generics.params.iter_mut().next() {
+        const_param.attrs.retain(|attr| !is_const_generic_marker(attr));
+    }
     if is_not_mockable(&item_impl.attrs) {
         return
     }
     let builder = match item_impl.trait_ {
-        Some((_, ref path, _)) => FnHeaderBuilder::TraitImpl(&path.segments),
+        // Drop mocks are stored by type ID, which ignores lifetimes, so non-'static types can't be mocked soundly
+        Some((_, ref path, _)) if is_drop_trait(&path.segments) && !is_self_static(item_impl) => return,
+        Some((_, ref path, _)) if is_drop_trait(&path.segments) => FnHeaderBuilder::DropImpl,
+        Some((_, ref path, _)) => FnHeaderBuilder::TraitImpl(&path.segments, is_self_static(item_impl)),
         None => FnHeaderBuilder::StructImpl,
     };
+    let fn_path_prefix = build_impl_fn_path_prefix(item_impl);
     for impl_item in &mut item_impl.items {
         if let ImplItem::Method(ref mut item_method) = *impl_item {
-            if is_impl_fn_mockabile(&builder, item_method) {
-                inject_any_method(&builder, &item_method.attrs, &mut item_method.sig, &mut item_method.block);
+            if is_impl_fn_selected(&builder, config, item_method) {
+                inject_any_method(&builder, config, fn_path_prefix.as_deref(), &item_method.attrs,
+                                  &mut item_method.sig, &mut item_method.block);
             }
         }
     }
 }
 
-fn is_impl_fn_mockabile(builder: &FnHeaderBuilder, item_method: &ImplItemMethod) -> bool {
-    if let FnHeaderBuilder::TraitImpl(ref segments) = *builder {
-        if let Some(pair) = segments.last() {
-            let segment = pair.value();
-            if segment.arguments.is_empty() && segment.ident == "Drop" {
-                if item_method.sig.ident == "drop" {
-                    return false
-                }
-            }
+/// Builds prefix of paths of functions in struct impl, which can be used outside of it, e.g. `<Struct>::`.
+/// Trait impls and impls with generic parameters other than lifetimes have none, lifetimes are replaced with `'static`.
+/// Trait methods aren't registered, because they can't be told apart from default methods, which can't be.
+fn build_impl_fn_path_prefix(item_impl: &ItemImpl) -> Option<String> {
+    let has_only_lifetime_params = item_impl.generics.params.iter()
+        .all(|param| matches!(*param, GenericParam::Lifetime(_)));
+    match item_impl.trait_.is_none() && has_only_lifetime_params {
+        true => Some(format!("<{}>::", make_lifetimes_static(item_impl.self_ty.clone().into_token_stream()))),
+        false => None,
+    }
+}
+
+fn make_lifetimes_static(token_stream: TokenStream) -> TokenStream {
+    let mut is_lifetime = false;
+    token_stream.into_iter()
+        .map(|token| {
+            let token = match token {
+                TokenTree::Group(ref group) if !is_lifetime => {
+                    let mut made_static = Group::new(group.delimiter(), make_lifetimes_static(group.stream()));
+                    made_static.set_span(group.span());
+                    TokenTree::Group(made_static)
+                },
+                TokenTree::Ident(ref ident) if is_lifetime => TokenTree::Ident(Ident::new("static", ident.span())),
+                token => token,
+            };
+            is_lifetime = matches!(token, TokenTree::Punct(ref punct) if punct.as_char() == '\'');
+            token
+        })
+        .collect()
+}
+
+/// Checks if implementing type is known to be `'static`: impl has no generic type or lifetime parameters
+/// and the type has no lifetimes other than `'static`, including elided lifetimes of references
+fn is_self_static(item_impl: &ItemImpl) -> bool {
+    item_impl.generics.params.iter().all(|param| matches!(*param, GenericParam::Const(_)))
+        && has_only_static_lifetimes(item_impl.self_ty.clone().into_token_stream())
+}
+
+fn has_only_static_lifetimes(token_stream: TokenStream) -> bool {
+    let mut tokens = token_stream.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        let is_static = match token {
+            TokenTree::Group(group) => has_only_static_lifetimes(group.stream()),
+            TokenTree::Punct(ref punct) if punct.as_char() == '\'' =>
+                matches!(tokens.peek(), Some(TokenTree::Ident(ident)) if ident == "static"),
+            TokenTree::Punct(ref punct) if punct.as_char() == '&' =>
+                is_punct(tokens.peek(), '\'') || is_punct(tokens.peek(), '&'),
+            _ => true,
+        };
+        if !is_static {
+            return false
         }
     }
     true
 }
 
+fn is_impl_fn_selected(builder: &FnHeaderBuilder, config: &InjectorConfig, item_method: &ImplItemMethod) -> bool {
+    let is_method_pub = match *builder {
+        FnHeaderBuilder::TraitImpl(..) | FnHeaderBuilder::DropImpl => true,
+        _ => is_pub(&item_method.vis),
+    };
+    config.is_fn_selected(&item_method.sig.ident, is_method_pub)
+}
 
-fn inject_any_method(builder: &FnHeaderBuilder, attrs: &Vec<Attribute>, sig: &mut MethodSig, block: &mut Block) {
-    inject_any_fn(builder, attrs, &sig.constness, &sig.unsafety, &sig.ident, &mut sig.decl, block)
+fn is_pub(vis: &Visibility) -> bool {
+    matches!(*vis, Visibility::Public(_))
 }
 
-fn inject_any_fn(builder: &FnHeaderBuilder, attrs: &Vec<Attribute>, constness: &Option<Const>,
-                 unsafety: &Option<Unsafe>, fn_name: &Ident, fn_decl: &mut FnDecl, block: &mut Block) {
-    if constness.is_some() || unsafety.is_some() || fn_decl.variadic.is_some() || is_not_mockable(attrs) {
-        return
+fn is_drop_trait(segments: &Punctuated<PathSegment, Colon2>) -> bool {
+    match segments.last() {
+        Some(pair) => pair.value().arguments.is_empty() && pair.value().ident == "Drop",
+        None => false,
     }
+}
+
+
+fn inject_foreign_mod(foreign_mod: &mut ItemForeignMod, config: &InjectorConfig) -> Vec<Item> {
+    if is_not_mockable(&foreign_mod.attrs) || !config.is_safe() {
+        return vec![]
+    }
+    let mut shims = vec![];
+    let mut kept_items = vec![];
+    for foreign_item in foreign_mod.items.drain(..) {
+        match foreign_item {
+            ForeignItem::Fn(ref foreign_fn) if is_foreign_fn_mockable(foreign_fn, config) => {
+                let (renamed_fn, mut shim) = build_foreign_fn_shim(foreign_fn, config.is_unlinked());
+                let companion_items = inject_fn(&mut shim, config);
+                shims.push(Item::Fn(shim));
+                shims.extend(companion_items);
+                if !config.is_unlinked() {
+                    kept_items.push(ForeignItem::Fn(renamed_fn));
+                }
+            },
+            foreign_item => kept_items.push(foreign_item),
+        }
+    }
+    foreign_mod.items = kept_items;
+    if config.is_unlinked() {
+        foreign_mod.attrs.retain(|attr| !attr.path.is_ident("link"));
+    }
+    shims
+}
+
+fn is_foreign_fn_mockable(foreign_fn: &ForeignItemFn, config: &InjectorConfig) -> bool {
+    foreign_fn.decl.variadic.is_none() && !is_not_mockable(&foreign_fn.attrs)
+        && config.is_fn_selected(&foreign_fn.ident, is_pub(&foreign_fn.vis))
+}
+
+/// Returns foreign function renamed, but still linked to its original symbol and a safe shim calling it.
+/// Only extern blocks with `safe` argument get shims, because the foreign functions may not be safe to call.
+fn build_foreign_fn_shim(foreign_fn: &ForeignItemFn, is_unlinked: bool) -> (ForeignItemFn, ItemFn) {
+    let mut renamed_fn = foreign_fn.clone();
+    name_fn_args(&mut renamed_fn.decl.inputs);
+    renamed_fn.ident = Ident::new(&format!("__mocktopus_extern_{}__", foreign_fn.ident), foreign_fn.ident.span());
+    renamed_fn.vis = Visibility::Inherited;
+    if !foreign_fn.attrs.iter().any(|attr| attr.path.is_ident("link_name")) {
+        let link_name = foreign_fn.ident.to_string();
+        renamed_fn.attrs.push(parse_quote!(#[link_name = #link_name]));
+    }
+    let shim_attrs = foreign_fn.attrs.iter()
+        .filter(|attr| attr.path.is_ident("doc") || attr.path.is_ident("cfg"));
+    let FnDecl { ref generics, ref inputs, ref output, .. } = *renamed_fn.decl;
+    let shim_body: Block = match is_unlinked {
+        false => {
+            let renamed_ident = &renamed_fn.ident;
+            let arg_names = iter_arg_names(inputs);
+            parse_quote!({
+                unsafe {
+                    #renamed_ident(#(#arg_names),*)
+                }
+            })
+        },
+        true => {
+            let panic_msg = format!("extern function {} was called, but it's not mocked", foreign_fn.ident);
+            parse_quote!({
+                panic!(#panic_msg)
+            })
+        },
+    };
+    let ForeignItemFn { ref vis, ref ident, .. } = *foreign_fn;
+    let shim = parse_quote! {
+        #(#shim_attrs)*
+        #[allow(unused_variables)]
+        #vis fn #ident #generics(#inputs) #output #shim_body
+    };
+    (renamed_fn, shim)
+}
+
+fn name_fn_args(inputs: &mut Punctuated<FnArg, Comma>) {
+    for (i, fn_arg) in inputs.iter_mut().enumerate() {
+        let ty = match *fn_arg {
+            FnArg::Captured(ArgCaptured { pat: Pat::Ident(_), .. }) => continue,
+            FnArg::Captured(ArgCaptured { ref ty, .. }) | FnArg::Ignored(ref ty) => ty.clone(),
+            _ => continue,
+        };
+        let arg_ident = Ident::new(&format!("__mocktopus_arg_{}__", i), Span::call_site());
+        *fn_arg = parse_quote!(#arg_ident: #ty);
+    }
+}
+
+fn iter_arg_names(inputs: &Punctuated<FnArg, Comma>) -> impl Iterator<Item = &Ident> {
+    inputs.iter()
+        .filter_map(|fn_arg| match *fn_arg {
+            FnArg::Captured(ArgCaptured { pat: Pat::Ident(PatIdent { ref ident, .. }), .. }) => Some(ident),
+            _ => None,
+        })
+}
+
+fn inject_any_method(builder: &FnHeaderBuilder, config: &InjectorConfig, fn_path_prefix: Option<&str>,
+                     attrs: &[Attribute], sig: &mut MethodSig, block: &mut Block) {
+    if is_fn_injectable(attrs, &sig.constness, &sig.unsafety, &sig.decl) {
+        inject_any_fn(builder, config, fn_path_prefix, &sig.ident, &mut sig.decl, block);
+    }
+}
+
+fn is_fn_injectable(attrs: &[Attribute], constness: &Option<Const>, unsafety: &Option<Unsafe>, fn_decl: &FnDecl)
+        -> bool {
+    constness.is_none() && unsafety.is_none() && fn_decl.variadic.is_none() && !is_not_mockable(attrs)
+}
+
+/// Function is registered as mockable if it has no generic parameters other than lifetimes, including ones
+/// of `impl Trait` arguments, and its path can be built by prefixing its name with `fn_path_prefix`
+fn inject_any_fn(builder: &FnHeaderBuilder, config: &InjectorConfig, fn_path_prefix: Option<&str>, fn_name: &Ident,
+                 fn_decl: &mut FnDecl, block: &mut Block) {
     unignore_fn_args(&mut fn_decl.inputs);
-    let header_stmt = builder.build(fn_name, fn_decl, block.brace_token.span);
+    let has_only_lifetime_params = fn_decl.generics.params.iter()
+        .all(|param| matches!(*param, GenericParam::Lifetime(_)));
+    let has_impl_trait_args = fn_decl.inputs.iter()
+        .any(|fn_arg| matches!(*fn_arg, FnArg::Captured(ArgCaptured { ref ty, .. }) if contains_impl_trait(ty)));
+    let registered_path = fn_path_prefix
+        .filter(|_| has_only_lifetime_params && !has_impl_trait_args)
+        .map(|fn_path_prefix| format!("{}{}", fn_path_prefix, fn_name));
+    let header_stmt = builder.build(fn_name, fn_decl, block.brace_token.span, config.crate_path(),
+                                    registered_path.as_deref());
     block.stmts.insert(0, header_stmt);
 }
 
@@ -114,9 +359,9 @@ fn unignore_fn_args(inputs: &mut Punctuated<FnArg, Comma>) {
     }
 }
 
-const INJECTOR_STOPPER_ATTRS: [&str; 2] = ["mockable", "not_mockable"];
+const INJECTOR_STOPPER_ATTRS: [&str; 3] = ["mockable", "mockable_trait", "not_mockable"];
 
-fn is_not_mockable(attrs: &Vec<Attribute>) -> bool {
+fn is_not_mockable(attrs: &[Attribute]) -> bool {
     attrs.iter()
         .filter_map(|a| a.path.segments.last())
         .map(|s| s.value().ident.to_string())
@@ -3,18 +3,32 @@
     html_favicon_url = "https://raw.githubusercontent.com/CodeSandwich/mocktopus/master/logo.png")]
 
 #![feature(proc_macro_diagnostic)]
+// Doc examples show tests, they are never run as doctests
+#![allow(clippy::test_attr_in_doctest)]
 
 extern crate proc_macro;
 extern crate proc_macro2;
 extern crate syn;
 extern crate quote;
 
+mod companion_builder;
 mod display_delegate;
+mod extern_wrapper_builder;
 mod item_injector;
+mod mock_rules_builder;
+mod injector_config;
 mod header_builder;
+mod placement_checker;
+mod static_wrapper_builder;
+mod test_builder;
+mod trait_mock_builder;
 
+use crate::injector_config::InjectorConfig;
+use crate::placement_checker::Placement;
 use proc_macro::{Span, TokenStream};
 use quote::ToTokens;
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
 
 /// Procedural macro, makes items and their sub-items mockable
 ///
@@ -51,6 +65,16 @@ use quote::ToTokens;
 ///     fn mockable() { ... }
 /// }
 /// ```
+/// - `Drop` impl blocks (makes `drop` mockable with
+///   [mock_drop](https://docs.rs/mocktopus/*/mocktopus/mocking/fn.mock_drop.html), because it can't be referenced),
+///   only if the impl has no generic type or lifetime parameters and the type is `'static`
+///
+/// ```
+/// #[mockable]
+/// impl Drop for Structure {
+///     fn drop(&mut self) { ... }
+/// }
+/// ```
 /// - traits (makes all default functions inside mockable)
 ///
 /// ```
@@ -59,6 +83,52 @@ use quote::ToTokens;
 ///     fn mockable() { ... }
 /// }
 /// ```
+/// - extern blocks with `safe` argument (makes all non-variadic functions inside callable
+///   through safe mockable shims)
+///
+/// ```
+/// #[mockable(safe)]
+/// extern "C" {
+///     fn mockable(arg: i32) -> i32;
+/// }
+///
+/// fn call_mockable() -> i32 {
+///     mockable(1) // no unsafe block needed
+/// }
+/// ```
+/// The shims have names and visibility of the original functions, which are renamed, but still linked
+/// to the same symbols. Extern functions may require their arguments to be valid, e.g. pointers,
+/// so `safe` must be used only for functions, which are safe to call with any arguments.
+///
+/// Functions with `impl Trait` in argument types keep their signatures, so their callers aren't affected.
+/// Types hidden behind `impl Trait` can't be specified with turbofish, so they're inferred from types
+/// of arguments of mock closure:
+///
+/// ```
+/// #[mockable]
+/// fn mockable(arg: impl Display) { ... }
+///
+/// mockable.mock_safe(|_: u32| MockResult::Return(()));
+/// ```
+/// Functions returning `impl Trait` can be mocked to return a value with
+/// [MockableImplTrait](https://docs.rs/mocktopus/*/mocktopus/mocking/trait.MockableImplTrait.html).
+///
+/// Standalone functions and extern shims get a hidden companion struct named `__mock_` followed by function name.
+/// Its fields are named after function arguments, unnamed ones become `arg0`, `arg1` and so on.
+/// The struct has typed mock helpers `returns`, `calls` and `expect` with visibility of the function,
+/// but never more than `pub(crate)`, so companions don't become a part of the crate API:
+///
+/// ```
+/// #[mockable]
+/// fn mockable(key: &str, value: u32) -> bool { ... }
+///
+/// __mock_mockable::calls(|args| args.key.len() as u32 == args.value);
+/// ```
+/// It's not generated for functions with generics, named lifetimes other than `'static`
+/// or `impl Trait` in signature. Functions and methods in impl blocks and traits get no companions,
+/// because a struct can't be placed next to them, they can be mocked only with
+/// [Mockable](https://docs.rs/mocktopus/*/mocktopus/mocking/trait.Mockable.html).
+///
 /// # Invalid to annotate **(WILL FAIL TO COMPILE OR BREAK MOCKING!)**
 ///
 /// - single functions in struct impls
@@ -85,6 +155,10 @@ use quote::ToTokens;
 ///     fn mockable() { ... }
 /// }
 /// ```
+///
+/// These usages are reported as compilation errors. When function has no `self` argument and doesn't use `Self`,
+/// its placement is found in its source file, so it's not reported when the annotation comes from a macro expansion.
+///
 /// # Indifferent to annotate
 /// - already mockable items (inside annotated modules)
 /// - module declarations
@@ -95,22 +169,558 @@ use quote::ToTokens;
 /// ```
 /// - const functions (they are impossible to mock)
 /// - unsafe functions (they are impossible to mock)
+/// - extern blocks without `safe` argument (their functions are unsafe)
+/// - variadic functions (they are impossible to mock)
 /// - any macro generated items (they are impossible to mock)
 /// - any other items
+///
+/// Directly annotated module declarations, const, unsafe and variadic functions, macros and extern blocks
+/// without `safe` argument raise warnings.
+///
+/// # Arguments
+/// Arguments select, which functions inside of annotated item are made mockable.
+/// They apply to all nested items and can be combined, then function must match all of them.
+/// - `only(...)` makes mockable only functions with listed names
+///
+/// ```
+/// #[mockable(only(mockable_1, mockable_2))]
+/// mod module {
+///     fn mockable_1() { ... }
+///     fn mockable_2() { ... }
+///     fn not_mockable() { ... }
+/// }
+/// ```
+/// - `except(...)` makes mockable all functions except ones with listed names
+///
+/// ```
+/// #[mockable(except(not_mockable))]
+/// impl Structure {
+///     fn mockable() { ... }
+///     fn not_mockable() { ... }
+/// }
+/// ```
+/// - `pub_only` makes mockable only `pub` functions. Functions in trait impls are always considered public,
+///   trait default functions are considered public when trait is `pub`.
+///
+/// ```
+/// #[mockable(pub_only)]
+/// mod module {
+///     pub fn mockable() { ... }
+///     fn not_mockable() { ... }
+/// }
+/// ```
+/// - `name = "..."` makes mockable only functions with names matching pattern, in which `*` matches any text
+///
+/// ```
+/// #[mockable(name = "db_*")]
+/// mod module {
+///     fn db_get() { ... }
+///     fn db_put() { ... }
+///     fn not_mockable() { ... }
+/// }
+/// ```
+/// - `safe` makes functions of extern blocks mockable through safe shims. They're unsafe to call,
+///   because they may have requirements for their arguments, so it's up to the user to make sure that they don't.
+///
+/// ```
+/// #[mockable(safe)]
+/// extern "C" {
+///     fn abs(value: i32) -> i32;
+/// }
+/// ```
+/// - `unlinked` makes shims of extern functions not call them and panic unless mocked.
+///   The extern functions are removed and so are `#[link]` attributes of extern blocks,
+///   which allows testing code using libraries not available in test environment.
+///
+/// ```
+/// #[cfg_attr(test, mockable(safe, unlinked))]
+/// #[link(name = "hardware")]
+/// extern "C" {
+///     fn read_sensor() -> i32;
+/// }
+/// ```
+/// - `mocktopus = "..."` sets path to Mocktopus crate used by the injected code, by default it's
+///   `extern crate mocktopus`. This is needed when the crate is renamed or reexported under a different path.
+///
+/// ```
+/// #[mockable(mocktopus = "::test_utils::mocktopus")]
+/// fn mockable() { ... }
+/// ```
+#[proc_macro_attribute]
+pub fn mockable(args: TokenStream, token_stream: TokenStream) -> TokenStream {
+    let config = match parse_injector_config(args) {
+        Ok(config) => config,
+        Err(err) => {
+            Span::call_site()
+                .error(format!("Invalid mockable arguments: {}", err))
+                .emit();
+            return token_stream;
+        }
+    };
+    let mut item = match item_injector::parse_item(token_stream.clone().into()) {
+        Ok(item) => item,
+        Err(err) => {
+            let placement = placement_checker::check_unparsable(token_stream.clone().into());
+            if !emit_placement_diagnostic(placement) {
+                Span::call_site()
+                    .warning("Failed to make code mockable")
+                    .error(format!("Failed to parse: {}", err))
+                    .emit();
+            }
+            return token_stream;
+        }
+    };
+    let placement = placement_checker::check_item(&item, &config, Span::call_site());
+    if emit_placement_diagnostic(placement) {
+        return token_stream;
+    }
+    let companion_items = item_injector::inject_item(&mut item, &config);
+    let mut output = proc_macro2::TokenStream::new();
+    item.to_tokens(&mut output);
+    for companion_item in companion_items {
+        companion_item.to_tokens(&mut output);
+    }
+    output.into()
+}
+
+/// Returns true if item should be left unchanged
+fn emit_placement_diagnostic(placement: Placement) -> bool {
+    match placement {
+        Placement::Valid => false,
+        Placement::Ignored(reason) => {
+            Span::call_site()
+                .warning(format!("#[mockable] has no effect: {}", reason))
+                .emit();
+            true
+        },
+        Placement::Invalid(error, help) => {
+            Span::call_site()
+                .error(error)
+                .help(help)
+                .emit();
+            true
+        },
+    }
+}
+
+fn parse_injector_config(args: TokenStream) -> Result<InjectorConfig, String> {
+    let nested_metas = Punctuated::parse_terminated.parse(args)
+        .map_err(|err| err.to_string())?;
+    InjectorConfig::parse(&nested_metas)
+}
+
+/// Procedural macro, generates mock implementation of annotated trait
+///
+/// The generated struct is named `Mock` + trait name and implements the annotated trait.
+/// All its methods are mockable and unless mocked they panic. The default methods of the trait are
+/// made mockable the same way as with `#[mockable]` annotation.
+/// This allows testing code using trait objects or generics bound by the trait without writing fake implementations.
+///
+/// ```
+/// #[mockable_trait]
+/// pub trait Store {
+///     fn get(&self, key: u32) -> Option<String>;
+/// }
+///
+/// fn read(store: &dyn Store) -> String {
+///     store.get(1).unwrap_or_default()
+/// }
+///
+/// #[test]
+/// fn read_test() {
+///     MockStore::get.mock_safe(|_, key| MockResult::Return(Some(format!("value {}", key))));
+///
+///     assert_eq!("value 1", read(&MockStore::new()));
+/// }
+/// ```
+/// Generic traits are supported, the mock struct has same type and lifetime parameters as the trait.
+/// Traits with associated types, associated consts without default values or const generics are not supported.
+/// If trait has supertraits, they must be implemented for the mock struct manually.
+#[proc_macro_attribute]
+pub fn mockable_trait(_: TokenStream, token_stream: TokenStream) -> TokenStream {
+    let mut item_trait: syn::ItemTrait = match syn::parse(token_stream.clone()) {
+        Ok(item_trait) => item_trait,
+        Err(err) => {
+            Span::call_site()
+                .error(format!("#[mockable_trait] can annotate only traits: {}", err))
+                .emit();
+            return token_stream;
+        }
+    };
+    let mock_items = match trait_mock_builder::build_trait_mock(&item_trait) {
+        Ok(mock_items) => mock_items,
+        Err(err) => {
+            Span::call_site()
+                .error(format!("Failed to generate trait mock: {}", err))
+                .emit();
+            return token_stream;
+        }
+    };
+    let config = InjectorConfig::default();
+    item_injector::inject_trait(&mut item_trait, &config);
+    let mut output = item_trait.into_token_stream();
+    for mut mock_item in mock_items {
+        item_injector::inject_item(&mut mock_item, &config);
+        mock_item.to_tokens(&mut output);
+    }
+    output.into()
+}
+
+/// Procedural macro, makes static values mockable
+///
+/// Annotated static is replaced with a wrapper, which gives access to the value and can be mocked in current thread.
+/// Mocks are set with `mock_static` method and they are active until returned guard is dropped.
+/// Mock values are leaked, so they can be safely borrowed for `'static` lifetime.
+///
+/// # Valid to annotate
+/// - static items (value is accessed with dereference)
+///
+/// ```
+/// #[mockable_static]
+/// static LIMIT: u32 = 10;
+///
+/// fn is_over_limit(value: u32) -> bool {
+///     value > *LIMIT
+/// }
+///
+/// #[test]
+/// fn is_over_limit_test() {
+///     let _guard = LIMIT.mock_static(1);
+///
+///     assert!(is_over_limit(2));
+/// }
+/// ```
+/// - `lazy_static!` declarations (value is accessed with dereference)
+///
+/// ```
+/// #[mockable_static]
+/// lazy_static! {
+///     static ref CONFIG: Config = Config::load();
+/// }
+/// ```
+/// - `thread_local!` declarations (value is accessed with `with` and `try_with` methods)
+///
+/// ```
+/// #[mockable_static]
+/// thread_local! {
+///     static COUNTER: Cell<u32> = Cell::new(0);
+/// }
+/// ```
+/// Mutable statics are not supported. The annotated static is no longer of its declared type,
+/// so it can't be used in constant expressions like initializers of other statics.
+/// Linkage attributes `#[no_mangle]`, `#[export_name]`, `#[used]` and `#[link_section]` are rejected,
+/// `#[cfg]` attributes apply to the wrapper too.
+///
+/// The only argument is `mocktopus = "..."`, which works like in [mockable](attr.mockable.html).
 #[proc_macro_attribute]
-pub fn mockable(_: TokenStream, token_stream: TokenStream) -> TokenStream {
-    let mut item: syn::Item = match syn::parse(token_stream.clone()) {
+pub fn mockable_static(args: TokenStream, token_stream: TokenStream) -> TokenStream {
+    let config = match Punctuated::parse_terminated.parse(args) {
+        Ok(nested_metas) => InjectorConfig::parse_crate_path(&nested_metas),
+        Err(err) => Err(err.to_string()),
+    };
+    let config = match config {
+        Ok(config) => config,
+        Err(err) => {
+            Span::call_site()
+                .error(format!("Invalid mockable_static arguments: {}", err))
+                .emit();
+            return token_stream;
+        }
+    };
+    let item: syn::Item = match syn::parse(token_stream.clone()) {
         Ok(item) => item,
         Err(err) => {
             Span::call_site()
-                .warning("Failed to make code mockable")
-                .error(format!("Failed to parse: {}", err))
+                .error(format!("#[mockable_static] can annotate only statics: {}", err))
                 .emit();
             return token_stream;
         }
     };
-    item_injector::inject_item(&mut item);
-    item.into_token_stream().into()
+    match static_wrapper_builder::build_static_wrappers(&item, &config) {
+        Ok(output) => output.into(),
+        Err(err) => {
+            Span::call_site()
+                .error(format!("Failed to make static mockable: {}", err))
+                .emit();
+            token_stream
+        }
+    }
+}
+
+/// Procedural macro, generates mockable wrapper of function, which can't be annotated, e.g. from external crate
+///
+/// Annotated item is a function declaration without body, which gets replaced with a mockable function
+/// calling function under `path` with all its arguments. The declared signature must be compatible with
+/// wrapped function, but it doesn't have to be identical, e.g. it may be less generic.
+///
+/// ```
+/// #[mockable_wrap(path = "std::fs::read_to_string")]
+/// pub fn read_to_string(path: &str) -> std::io::Result<String>;
+///
+/// #[test]
+/// fn read_to_string_test() {
+///     read_to_string.mock_safe(|_| MockResult::Return(Ok("mocked".to_string())));
+///
+///     assert_eq!("mocked", read_to_string("/not/existing").unwrap());
+/// }
+/// ```
+/// Arguments must be simple names, const, unsafe, async and variadic functions can't be wrapped.
+/// To wrap many functions from a single module, use [mock_extern!](macro.mock_extern.html).
+#[proc_macro_attribute]
+pub fn mockable_wrap(args: TokenStream, token_stream: TokenStream) -> TokenStream {
+    let path = match parse_wrapped_path(args) {
+        Ok(path) => path,
+        Err(err) => {
+            Span::call_site()
+                .error(format!("Invalid mockable_wrap arguments: {}", err))
+                .emit();
+            return token_stream;
+        }
+    };
+    let wrapped_fn = match syn::parse(token_stream.clone()) {
+        Ok(wrapped_fn) => wrapped_fn,
+        Err(err) => {
+            Span::call_site()
+                .error(format!("#[mockable_wrap] can annotate only function declarations without body: {}", err))
+                .emit();
+            return token_stream;
+        }
+    };
+    match extern_wrapper_builder::build_declared_wrapper_fn(&wrapped_fn, &path) {
+        Ok(wrapper) => wrapper.into_token_stream().into(),
+        Err(err) => {
+            Span::call_site()
+                .error(format!("Failed to generate wrapper: {}", err))
+                .emit();
+            token_stream
+        }
+    }
+}
+
+fn parse_wrapped_path(args: TokenStream) -> Result<syn::Path, String> {
+    let nested_metas: Punctuated<syn::NestedMeta, syn::token::Comma> = Punctuated::parse_terminated.parse(args)
+        .map_err(|err| err.to_string())?;
+    let mut nested_metas = nested_metas.into_iter();
+    match (nested_metas.next(), nested_metas.next()) {
+        (Some(syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+            ref ident,
+            lit: syn::Lit::Str(ref path),
+            ..
+        }))), None) if ident == "path" => path.parse().map_err(|err| format!("invalid path: {}", err)),
+        _ => Err("expected single argument path = \"...\"".to_string()),
+    }
+}
+
+/// Procedural macro, generates modules mirroring external modules with some functions made mockable
+///
+/// Every generated module reexports all items of its external module. Listed functions are replaced with public
+/// mockable wrappers like ones generated by [mockable_wrap](attr.mockable_wrap.html).
+/// Code under test can then use generated module in test builds instead of the external one.
+/// Types in signatures are resolved inside of generated module, which imports all items of its parent module.
+///
+/// ```
+/// mocktopus::mock_extern! {
+///     pub mod fs = std::fs {
+///         fn read_to_string(path: &str) -> std::io::Result<String>;
+///         fn write(path: &str, contents: &[u8]) -> std::io::Result<()>;
+///     }
+/// }
+///
+/// #[cfg(test)]
+/// use self::fs;
+/// #[cfg(not(test))]
+/// use std::fs;
+///
+/// fn load() -> String {
+///     fs::read_to_string("data.txt").unwrap()
+/// }
+/// ```
+#[proc_macro]
+pub fn mock_extern(token_stream: TokenStream) -> TokenStream {
+    let wrapped_mods = match syn::parse(token_stream) {
+        Ok(wrapped_mods) => wrapped_mods,
+        Err(err) => {
+            Span::call_site()
+                .error(format!("Failed to parse mock_extern: {}, expected `mod name = path {{ fn declarations }}`",
+                               err))
+                .emit();
+            return TokenStream::new();
+        }
+    };
+    match extern_wrapper_builder::build_wrapped_mods(&wrapped_mods) {
+        Ok(output) => output.into(),
+        Err(err) => {
+            Span::call_site()
+                .error(format!("Failed to generate wrappers: {}", err))
+                .emit();
+            TokenStream::new()
+        }
+    }
+}
+
+/// Procedural macro, sets up mocks declared with rules matching arguments, reexported as `mocktopus::mock`
+///
+/// Each rule is a path to mockable function followed by patterns of its arguments in parentheses,
+/// an optional `if` guard and an action after `=>`:
+///
+/// - `continue` runs the function normally, `continue(a, b)` runs it with replaced arguments.
+///   Patterns of rules with plain `continue` bind arguments by reference, because the arguments are passed on
+/// - `panic("format", args)` panics with a formatted message
+/// - any other expression is returned
+///
+/// Rules of a function are combined into a single mock set with
+/// [mock_safe](https://docs.rs/mocktopus/*/mocktopus/mocking/trait.Mockable.html#tymethod.mock_safe),
+/// they are checked in order and the first matching one is used. `..` matches any remaining arguments.
+/// When no rule matches, the mocked function panics with a message listing its rules.
+///
+/// ```
+/// #[test]
+/// fn read_only_db_test() {
+///     mock! {
+///         db::get(1) => Some("a"),
+///         db::get(key) if key > 100 => panic("key {} out of range", key),
+///         db::get(_) => continue,
+///         db::put(..) => panic("read-only"),
+///     }
+///
+///     assert_eq!(Some("a"), db::get(1));
+/// }
+/// ```
+/// Like in mock closures, values used in returned expressions must be `'static`, they are moved into the mock.
+#[proc_macro]
+pub fn mock(token_stream: TokenStream) -> TokenStream {
+    match syn::parse(token_stream) {
+        Ok(mock_rules) => mock_rules_builder::build_mock_rules(&mock_rules).into(),
+        Err(err) => {
+            let err: syn::Error = err;
+            let message = format!("Failed to parse mock!: {}, expected `path::to::function(patterns) => action`",
+                                  err);
+            syn::Error::new(err.span(), message).to_compile_error().into()
+        }
+    }
+}
+
+/// Procedural macro, marks function as a test running with clean mocks, reexported as `mocktopus::mocktopus_test`
+///
+/// The annotated function becomes a `#[test]`, which clears all mocks of current thread before running,
+/// then verifies all pending expectations set with
+/// [mock_expect](https://docs.rs/mocktopus/*/mocktopus/mocking/trait.Mockable.html#tymethod.mock_expect)
+/// and clears the mocks again afterwards, even if the test panics.
+/// This keeps tests isolated when they are run on reused threads, e.g. by custom harnesses.
+///
+/// ```
+/// #[mockable]
+/// fn send(message: &str) { ... }
+///
+/// #[mocktopus::mocktopus_test]
+/// fn broadcast_test() {
+///     send.mock_expect(2, |_| MockResult::Return(()));
+///
+///     broadcast(&["first", "second"]);
+/// }
+/// ```
+/// Other attributes like `#[should_panic]` and returning `Result` are supported.
+/// Mocks listed in [#\[with_mocks\]](attr.with_mocks.html) are set after clearing.
+/// Test functions can't have arguments, so for fixtures or custom harnesses use
+/// [run_test](https://docs.rs/mocktopus/*/mocktopus/mocking/fn.run_test.html) directly.
+/// The macro is opt-in, it never replaces plain `#[test]`, even with `#[macro_use] extern crate mocktopus`.
+///
+/// With `strict` argument the test also fails if it sets a mock, which is never called, see
+/// [verify_mocks_called](https://docs.rs/mocktopus/*/mocktopus/mocking/fn.verify_mocks_called.html).
+/// Mocks of non-generic functions, which aren't mockable, make any test fail when they are set up,
+/// but mocks of generic ones are caught only by this check:
+///
+/// ```
+/// fn not_mockable<T>(value: T) -> T { ... }
+///
+/// #[mocktopus::mocktopus_test(strict)]
+/// fn not_mockable_test() { // fails, the mock has no effect
+///     not_mockable::<u32>.mock_safe(|_| MockResult::Return(1));
+///
+///     assert_eq!(1, not_mockable(2u32));
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn mocktopus_test(args: TokenStream, token_stream: TokenStream) -> TokenStream {
+    let is_strict = match args.to_string().as_str() {
+        ""          => false,
+        "strict"    => true,
+        _           => {
+            Span::call_site()
+                .error("#[mocktopus_test] accepts only argument `strict`")
+                .emit();
+            return token_stream;
+        }
+    };
+    let item_fn: syn::ItemFn = match syn::parse(token_stream.clone()) {
+        Ok(item_fn) => item_fn,
+        Err(err) => {
+            Span::call_site()
+                .error(format!("#[mocktopus_test] can annotate only functions: {}", err))
+                .emit();
+            return token_stream;
+        }
+    };
+    match test_builder::build_test(&item_fn, is_strict) {
+        Ok(output) => output.into(),
+        Err(err) => {
+            Span::call_site()
+                .error(format!("Failed to make test: {}", err))
+                .emit();
+            token_stream
+        }
+    }
+}
+
+/// Procedural macro, sets up mocks for duration of annotated test
+///
+/// Arguments are comma separated mocks in form of `path::to::function => mock`. If the mock is a closure,
+/// it's set like with
+/// [mock_scoped](https://docs.rs/mocktopus/*/mocktopus/mocking/trait.Mockable.html#tymethod.mock_scoped),
+/// otherwise it's a value evaluated once before the test, which is cloned and returned on every call.
+/// The mocks are removed when the test finishes, even if it panics.
+///
+/// ```
+/// #[test]
+/// #[with_mocks(
+///     hello_world::world => "mocking",
+///     config::load => |path| MockResult::Return(Config::for_path(path)),
+/// )]
+/// fn hello_world_test() {
+///     assert_eq!("hello mocking", hello_world::hello_world());
+/// }
+/// ```
+/// The annotated function must have no arguments, `#[test]` is not added automatically.
+#[proc_macro_attribute]
+pub fn with_mocks(args: TokenStream, token_stream: TokenStream) -> TokenStream {
+    let test_mocks = match syn::parse(args) {
+        Ok(test_mocks) => test_mocks,
+        Err(err) => {
+            let err: syn::Error = err;
+            let message = format!("Invalid with_mocks arguments: {}, expected `path::to::function => mock`", err);
+            let mut output = syn::Error::new(err.span(), message).to_compile_error();
+            output.extend(proc_macro2::TokenStream::from(token_stream));
+            return output.into();
+        }
+    };
+    let item_fn: syn::ItemFn = match syn::parse(token_stream.clone()) {
+        Ok(item_fn) => item_fn,
+        Err(err) => {
+            Span::call_site()
+                .error(format!("#[with_mocks] can annotate only functions: {}", err))
+                .emit();
+            return token_stream;
+        }
+    };
+    match test_builder::build_test_with_mocks(&test_mocks, &item_fn) {
+        Ok(output) => output.into(),
+        Err(err) => {
+            Span::call_site()
+                .error(format!("Failed to set up mocks: {}", err))
+                .emit();
+            token_stream
+        }
+    }
 }
 
 /// Procedural macro, guards items from being made mockable by enclosing item.
@@ -0,0 +1,199 @@
+use crate::header_builder::MOCKTOPUS_CRATE_NAME;
+use proc_macro2::{Delimiter, Group, Ident, Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::{parenthesized, Expr, Pat, Path, Token};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+
+const ARGS_NAME: &str = "__mocktopus_args__";
+
+/// Pattern of a single argument or `..` matching any remaining arguments
+enum RuleArg {
+    Pat(Pat),
+    Rest(Token![..]),
+}
+
+impl ToTokens for RuleArg {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match *self {
+            RuleArg::Pat(ref pat) => pat.to_tokens(tokens),
+            RuleArg::Rest(ref rest) => rest.to_tokens(tokens),
+        }
+    }
+}
+
+enum RuleAction {
+    /// `continue` or `continue(new, args)`
+    Continue(Option<Punctuated<Expr, Token![,]>>),
+    /// `panic("format", args)`
+    Panic(TokenStream),
+    /// Any other expression is returned
+    Return(Expr),
+}
+
+/// `path::to::function(patterns) if guard => action`
+struct MockRule {
+    path:   Path,
+    paren:  Span,
+    args:   Vec<RuleArg>,
+    guard:  Option<Expr>,
+    action: RuleAction,
+}
+
+impl Parse for MockRule {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let path = input.parse()?;
+        let content;
+        let paren = parenthesized!(content in input).span;
+        let mut args = vec![];
+        while !content.is_empty() {
+            args.push(match content.peek(Token![..]) {
+                true    => RuleArg::Rest(content.parse()?),
+                false   => RuleArg::Pat(content.parse()?),
+            });
+            if !content.is_empty() {
+                content.parse::<Token![,]>()?;
+            }
+        }
+        let guard = match input.peek(Token![if]) {
+            true => {
+                input.parse::<Token![if]>()?;
+                Some(input.parse()?)
+            },
+            false => None,
+        };
+        input.parse::<Token![=>]>()?;
+        let action = if input.peek(Token![continue]) {
+            input.parse::<Token![continue]>()?;
+            match input.peek(syn::token::Paren) {
+                true => {
+                    let new_args;
+                    parenthesized!(new_args in input);
+                    RuleAction::Continue(Some(new_args.parse_terminated(Expr::parse)?))
+                },
+                false => RuleAction::Continue(None),
+            }
+        } else if is_panic_action(input) {
+            input.parse::<Ident>()?;
+            let message;
+            parenthesized!(message in input);
+            RuleAction::Panic(message.parse()?)
+        } else {
+            RuleAction::Return(input.parse()?)
+        };
+        Ok(MockRule { path, paren, args, guard, action })
+    }
+}
+
+fn is_panic_action(input: ParseStream) -> bool {
+    let fork = input.fork();
+    match fork.parse::<Ident>() {
+        Ok(ident) => ident == "panic" && fork.peek(syn::token::Paren),
+        Err(_) => false,
+    }
+}
+
+/// Content of `mock!` invocation, comma separated rules
+pub struct MockRules(Vec<MockRule>);
+
+impl Parse for MockRules {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let rules = input.parse_terminated::<_, Token![,]>(MockRule::parse)?;
+        Ok(MockRules(rules.into_iter().collect()))
+    }
+}
+
+pub fn build_mock_rules(mock_rules: &MockRules) -> TokenStream {
+    let crate_name = Ident::new(MOCKTOPUS_CRATE_NAME, Span::call_site());
+    let mut mocks = vec![];
+    for (path, rules) in group_by_function(&mock_rules.0) {
+        mocks.push(build_function_mock(&crate_name, path, &rules));
+    }
+    quote! {
+        {
+            extern crate mocktopus as #crate_name;
+            #(#mocks)*
+        }
+    }
+}
+
+/// Groups rules by function keeping order of rules and of first appearance of functions
+fn group_by_function(rules: &[MockRule]) -> Vec<(&Path, Vec<&MockRule>)> {
+    let mut groups: Vec<(String, &Path, Vec<&MockRule>)> = vec![];
+    for rule in rules {
+        let path_string = display_compact(&rule.path);
+        match groups.iter_mut().find(|group| group.0 == path_string) {
+            Some(group) => group.2.push(rule),
+            None => groups.push((path_string, &rule.path, vec![rule])),
+        }
+    }
+    groups.into_iter()
+        .map(|(_, path, rules)| (path, rules))
+        .collect()
+}
+
+fn build_function_mock(crate_name: &Ident, path: &Path, rules: &[&MockRule]) -> TokenStream {
+    let args_ident = Ident::new(ARGS_NAME, Span::call_site());
+    let arms = rules.iter().map(|rule| {
+        let pattern = build_args_pattern(&rule.args, rule.paren);
+        let guard = rule.guard.as_ref().map(|guard| quote!(if #guard));
+        let body = match rule.action {
+            // Arguments are matched by reference, so they can be passed on after the pattern bound some of them
+            RuleAction::Continue(None) => return quote! {
+                _ if match &#args_ident {
+                    #pattern #guard => true,
+                    #[allow(unreachable_patterns)]
+                    _ => false,
+                } => #crate_name::mocking::MockResult::Continue(#args_ident),
+            },
+            RuleAction::Continue(Some(ref new_args)) => {
+                let new_args = new_args.iter();
+                quote!(#crate_name::mocking::MockResult::Continue((#(#new_args,)*)))
+            },
+            RuleAction::Panic(ref message) => quote!(#crate_name::mocking::MockResult::Panic(format!(#message))),
+            RuleAction::Return(ref expr) => quote!(#crate_name::mocking::MockResult::Return(#expr)),
+        };
+        quote!(#pattern #guard => #body,)
+    });
+    let rule_strings: Vec<_> = rules.iter()
+        .map(|rule| {
+            let args = &rule.args;
+            display_compact(&quote!(#path(#(#args),*)))
+        })
+        .collect();
+    let no_match_message = format!("no mock! rule matches the call, rules: {}", rule_strings.join(", "));
+    quote! {
+        #crate_name::mocking::mock_tuple(&#path, move |#args_ident| match #args_ident {
+            #(#arms)*
+            #[allow(unreachable_patterns)]
+            _ => #crate_name::mocking::MockResult::Panic(#no_match_message.to_string()),
+        });
+    }
+}
+
+/// Creates pattern of arguments tuple, single argument needs trailing comma to be a tuple
+///
+/// The pattern has span of rule parentheses, so errors like wrong number of arguments point at them.
+fn build_args_pattern(args: &[RuleArg], paren: Span) -> TokenStream {
+    let has_rest = args.iter().any(|arg| match *arg {
+        RuleArg::Rest(_) => true,
+        RuleArg::Pat(_) => false,
+    });
+    let inner = match (args.len(), has_rest) {
+        (1, false) => quote!(#(#args)*,),
+        _ => quote!(#(#args),*),
+    };
+    let mut group = Group::new(Delimiter::Parenthesis, inner);
+    group.set_span(paren);
+    group.into_token_stream()
+}
+
+fn display_compact<T: ToTokens>(tokens: &T) -> String {
+    tokens.into_token_stream().to_string()
+        .replace(" :: ", "::")
+        .replace(":: ", "::")
+        .replace(" (", "(")
+        .replace("( ", "(")
+        .replace(" )", ")")
+        .replace(" ,", ",")
+}
@@ -0,0 +1,200 @@
+use crate::injector_config::InjectorConfig;
+use proc_macro::Span;
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use std::fs;
+use std::iter::Peekable;
+use std::str::Chars;
+use syn::{FnArg, FnDecl, Item, ItemFn, TraitItemMethod};
+
+const SINGLE_METHOD_HELP: &str = "annotate whole enclosing impl block or trait instead, functions, which should not \
+    be mockable, can be annotated with #[not_mockable] or left out with #[mockable(except(...))]";
+
+pub enum Placement {
+    Valid,
+    Ignored(&'static str),
+    Invalid(&'static str, &'static str),
+}
+
+/// Checks item annotated with attribute placed at `attr_span`
+pub fn check_item(item: &Item, config: &InjectorConfig, attr_span: Span) -> Placement {
+    match *item {
+        Item::Fn(ref item_fn)                                   => check_fn(item_fn, attr_span),
+        Item::Mod(ref item_mod) if item_mod.content.is_none()   => Placement::Ignored(
+            "module declarations can't be made mockable, annotate module definition in its file instead"),
+        Item::Macro(_) | Item::Macro2(_)                        => Placement::Ignored(
+            "macro generated items can't be made mockable"),
+        Item::ForeignMod(_) if !config.is_safe()                => Placement::Ignored(
+            "extern functions are unsafe, they can be made mockable only with `safe` argument"),
+        _                                                       => Placement::Valid,
+    }
+}
+
+fn check_fn(item_fn: &ItemFn, attr_span: Span) -> Placement {
+    if is_method(&item_fn.decl) || is_in_impl_or_trait(attr_span) {
+        return Placement::Invalid("#[mockable] can't annotate single function in impl block or trait",
+                                  SINGLE_METHOD_HELP)
+    }
+    if item_fn.constness.is_some() {
+        return Placement::Ignored("const functions can't be made mockable")
+    }
+    if item_fn.unsafety.is_some() {
+        return Placement::Ignored("unsafe functions can't be made mockable")
+    }
+    if item_fn.decl.variadic.is_some() {
+        return Placement::Ignored("variadic functions can't be made mockable")
+    }
+    Placement::Valid
+}
+
+fn is_method(fn_decl: &FnDecl) -> bool {
+    let has_receiver = fn_decl.inputs.iter()
+        .any(|fn_arg| matches!(*fn_arg, FnArg::SelfRef(_) | FnArg::SelfValue(_)));
+    let mut signature_tokens = fn_decl.inputs.clone().into_token_stream();
+    fn_decl.output.to_tokens(&mut signature_tokens);
+    fn_decl.generics.where_clause.to_tokens(&mut signature_tokens);
+    has_receiver || contains_self_type(signature_tokens)
+}
+
+fn contains_self_type(token_stream: TokenStream) -> bool {
+    token_stream.into_iter()
+        .any(|token_tree| match token_tree {
+            TokenTree::Ident(ident) => ident == "Self",
+            TokenTree::Group(group) => contains_self_type(group.stream()),
+            _ => false,
+        })
+}
+
+/// Functions without `self` and `Self` in signature are indistinguishable from standalone functions,
+/// so block enclosing the attribute is found in its source file. If the source is unavailable, e.g. the attribute
+/// comes from a macro expansion, the function is assumed to be standalone.
+fn is_in_impl_or_trait(attr_span: Span) -> bool {
+    let source = match attr_span.local_file().and_then(|path| fs::read_to_string(path).ok()) {
+        Some(source) => source,
+        None => return false,
+    };
+    let attr_offset = source.split_inclusive('\n')
+        .take(attr_span.line().saturating_sub(1))
+        .map(str::len)
+        .sum::<usize>();
+    let attr_offset = source[attr_offset..].char_indices()
+        .nth(attr_span.column().saturating_sub(1))
+        .map_or(source.len(), |(column_offset, _)| attr_offset + column_offset);
+    match attr_span.source_text() {
+        Some(ref attr_text) if source[attr_offset..].starts_with(attr_text.as_str()) =>
+            is_last_open_block_impl_or_trait(&source[..attr_offset]),
+        _ => false,
+    }
+}
+
+/// Finds the last block, which is opened, but not closed in source code.
+/// It's an impl or a trait if the first keyword of its header is `impl` or `trait`, e.g. `pub unsafe trait Trait {`.
+fn is_last_open_block_impl_or_trait(source: &str) -> bool {
+    let mut open_blocks = vec![];
+    let mut header_keyword = None;
+    let mut nesting = 0usize;
+    let mut chars = source.chars().peekable();
+    while let Some(char) = chars.next() {
+        match char {
+            '/' if chars.peek() == Some(&'/') => skip_line_comment(&mut chars),
+            '/' if chars.peek() == Some(&'*') => skip_block_comment(&mut chars),
+            '"' => skip_string(&mut chars),
+            '\'' => skip_char_or_lifetime(&mut chars),
+            '(' | '[' => nesting += 1,
+            ')' | ']' => nesting = nesting.saturating_sub(1),
+            '{' => {
+                open_blocks.push(nesting == 0 && matches!(header_keyword.as_deref(), Some("impl") | Some("trait")));
+                header_keyword = None;
+                nesting = 0;
+            },
+            '}' | ';' => {
+                if char == '}' {
+                    open_blocks.pop();
+                }
+                header_keyword = None;
+                nesting = 0;
+            },
+            _ if char.is_alphanumeric() || char == '_' => {
+                let mut word = char.to_string();
+                while let Some(&next) = chars.peek().filter(|next| next.is_alphanumeric() || **next == '_') {
+                    word.push(next);
+                    chars.next();
+                }
+                match (word.as_str(), chars.peek()) {
+                    ("b", Some(&'"')) | ("c", Some(&'"')) => { chars.next(); skip_string(&mut chars) },
+                    ("r", Some(&'"')) | ("r", Some(&'#')) | ("br", Some(&'"')) | ("br", Some(&'#'))
+                        | ("cr", Some(&'"')) | ("cr", Some(&'#')) => skip_raw_string_or_ident(&mut chars),
+                    ("pub", _) | ("unsafe", _) | ("auto", _) | ("default", _) => (),
+                    _ if nesting == 0 && header_keyword.is_none() => header_keyword = Some(word),
+                    _ => (),
+                }
+            },
+            _ => (),
+        }
+    }
+    open_blocks.pop().unwrap_or(false)
+}
+
+fn skip_line_comment(chars: &mut Peekable<Chars>) {
+    chars.find(|&char| char == '\n');
+}
+
+fn skip_block_comment(chars: &mut Peekable<Chars>) {
+    chars.next();
+    let mut depth = 1;
+    while depth > 0 {
+        match (chars.next(), chars.peek()) {
+            (Some('/'), Some(&'*')) => { chars.next(); depth += 1 },
+            (Some('*'), Some(&'/')) => { chars.next(); depth -= 1 },
+            (None, _) => return,
+            _ => (),
+        }
+    }
+}
+
+fn skip_string(chars: &mut Peekable<Chars>) {
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' => { chars.next(); },
+            '"' => return,
+            _ => (),
+        }
+    }
+}
+
+fn skip_raw_string_or_ident(chars: &mut Peekable<Chars>) {
+    let mut hashes = 0;
+    while chars.next_if_eq(&'#').is_some() {
+        hashes += 1;
+    }
+    if chars.next_if_eq(&'"').is_none() {
+        return
+    }
+    while let Some(char) = chars.next() {
+        if char == '"' && (0..hashes).all(|_| chars.next_if_eq(&'#').is_some()) {
+            return
+        }
+    }
+}
+
+/// Skips char literal, e.g. `'a'` or `'\\n'`, or only the first character of lifetime name
+fn skip_char_or_lifetime(chars: &mut Peekable<Chars>) {
+    match chars.next() {
+        Some('\\') => {
+            chars.next();
+            chars.find(|&char| char == '\'');
+        },
+        Some(_) => {
+            chars.next_if_eq(&'\'');
+        },
+        None => (),
+    }
+}
+
+/// Checks item unparsable as `syn::Item`, `Placement::Valid` means that no placement problem was recognized
+pub fn check_unparsable(token_stream: TokenStream) -> Placement {
+    match syn::parse2::<TraitItemMethod>(token_stream) {
+        Ok(_) => Placement::Invalid("#[mockable] can't annotate single function in trait", SINGLE_METHOD_HELP),
+        Err(_) => Placement::Valid,
+    }
+}
@@ -0,0 +1,268 @@
+use crate::header_builder::{build_import_crate, MOCKTOPUS_CRATE_NAME};
+use crate::injector_config::InjectorConfig;
+use crate::item_injector;
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::parse_quote;
+use syn::{AngleBracketedGenericArguments, Attribute, Expr, GenericArgument, Ident, Item, ItemMacro, ItemStatic,
+          Lifetime, Path, PathArguments, Token, Type, Visibility};
+use syn::parse::{ParseStream, Parser};
+
+/// Attributes giving static a symbol, they can't be moved to the generated value and would apply to the wrapper
+const LINKAGE_ATTRS: [&str; 4] = ["no_mangle", "export_name", "used", "link_section"];
+
+enum StaticKind<'a> {
+    Static,
+    ThreadLocal(&'a Path),
+    LazyStatic(&'a Path),
+}
+
+struct StaticDecl {
+    attrs:  Vec<Attribute>,
+    vis:    Visibility,
+    ident:  Ident,
+    ty:     Type,
+    expr:   Expr,
+}
+
+impl StaticDecl {
+    fn parse_many(input: ParseStream, with_ref: bool) -> syn::Result<Vec<StaticDecl>> {
+        let mut decls = vec![];
+        while !input.is_empty() {
+            let attrs = input.call(Attribute::parse_outer)?;
+            let vis = input.parse()?;
+            input.parse::<Token![static]>()?;
+            if with_ref {
+                input.parse::<Token![ref]>()?;
+            }
+            let ident = input.parse()?;
+            input.parse::<Token![:]>()?;
+            let mut ty = input.parse()?;
+            make_elided_lifetimes_static(&mut ty);
+            input.parse::<Token![=]>()?;
+            let expr = input.parse()?;
+            if !input.is_empty() {
+                input.parse::<Token![;]>()?;
+            }
+            decls.push(StaticDecl { attrs, vis, ident, ty, expr });
+        }
+        Ok(decls)
+    }
+}
+
+pub fn build_static_wrappers(item: &Item, config: &InjectorConfig) -> Result<TokenStream, String> {
+    match *item {
+        Item::Static(ref item_static) => build_static_item_wrapper(item_static, config),
+        Item::Macro(ref item_macro) => build_macro_wrappers(item_macro, config),
+        _ => Err("only static items, thread_local! and lazy_static! declarations can be annotated".to_string()),
+    }
+}
+
+fn build_static_item_wrapper(item_static: &ItemStatic, config: &InjectorConfig) -> Result<TokenStream, String> {
+    if item_static.mutability.is_some() {
+        return Err(format!("mutable static '{}' is not supported", item_static.ident))
+    }
+    let mut decl = StaticDecl {
+        attrs:  item_static.attrs.clone(),
+        vis:    item_static.vis.clone(),
+        ident:  item_static.ident.clone(),
+        ty:     (*item_static.ty).clone(),
+        expr:   (*item_static.expr).clone(),
+    };
+    make_elided_lifetimes_static(&mut decl.ty);
+    build_wrapper(&StaticKind::Static, &decl, config)
+}
+
+fn build_macro_wrappers(item_macro: &ItemMacro, config: &InjectorConfig) -> Result<TokenStream, String> {
+    let path = &item_macro.mac.path;
+    let kind = match path.segments.last().map(|pair| pair.into_value().ident.to_string()) {
+        Some(ref name) if name == "thread_local" => StaticKind::ThreadLocal(path),
+        Some(ref name) if name == "lazy_static" => StaticKind::LazyStatic(path),
+        _ => return Err("only thread_local! and lazy_static! macros can be annotated".to_string()),
+    };
+    let with_ref = matches!(kind, StaticKind::LazyStatic(_));
+    let decls = (|input: ParseStream| StaticDecl::parse_many(input, with_ref))
+        .parse2(item_macro.mac.tts.clone())
+        .map_err(|err| format!("failed to parse declarations: {}", err))?;
+    decls.iter()
+        .map(|decl| build_wrapper(&kind, decl, config))
+        .collect()
+}
+
+fn build_wrapper(kind: &StaticKind, decl: &StaticDecl, config: &InjectorConfig) -> Result<TokenStream, String> {
+    let StaticDecl { ref attrs, ref vis, ref ident, ref ty, ref expr } = *decl;
+    if let Some(attr) = attrs.iter().find(|attr| is_attr_named(attr, &LINKAGE_ATTRS)) {
+        return Err(format!("static '{}' has linkage attribute #[{}], which can't be applied to its wrapper",
+                           ident, attr.path.clone().into_token_stream()))
+    }
+    let cfg_attrs = attrs.iter()
+        .filter(|attr| is_attr_named(attr, &["cfg"]));
+    let cfg_attrs = quote!(#(#cfg_attrs)*);
+    let wrapper_ident = Ident::new(&format!("__mocktopus_static_{}__", ident), ident.span());
+    let inner_ident = Ident::new(&format!("__MOCKTOPUS_STATIC_VALUE_{}__", ident), ident.span());
+    let doc = format!("Mockable wrapper of `{}`, see \
+        [mockable_static](https://docs.rs/mocktopus_macros/*/mocktopus_macros/attr.mockable_static.html)", ident);
+    let mut items: Vec<Item> = vec![
+        parse_quote! {
+            #(#attrs)*
+            #vis static #ident: #wrapper_ident = #wrapper_ident;
+        },
+        parse_quote! {
+            #cfg_attrs
+            #[doc = #doc]
+            #[allow(non_camel_case_types)]
+            #[derive(Clone, Copy)]
+            #vis struct #wrapper_ident;
+        },
+    ];
+    let (mut accessor, access_methods): (Item, TokenStream) = match *kind {
+        StaticKind::Static => {
+            let accessor = parse_quote! {
+                impl #wrapper_ident {
+                    #[doc(hidden)]
+                    pub fn __mocktopus_get__() -> &'static #ty {
+                        static #inner_ident: #ty = #expr;
+                        &#inner_ident
+                    }
+                }
+            };
+            (accessor, build_deref_impl(&wrapper_ident, ty))
+        },
+        StaticKind::LazyStatic(path) => {
+            items.push(parse_quote! {
+                #cfg_attrs
+                #path! {
+                    static ref #inner_ident: #ty = #expr;
+                }
+            });
+            let accessor = parse_quote! {
+                impl #wrapper_ident {
+                    #[doc(hidden)]
+                    pub fn __mocktopus_get__() -> &'static #ty {
+                        &*#inner_ident
+                    }
+                }
+            };
+            (accessor, build_deref_impl(&wrapper_ident, ty))
+        },
+        StaticKind::ThreadLocal(path) => {
+            items.push(parse_quote! {
+                #cfg_attrs
+                #path! {
+                    static #inner_ident: #ty = #expr;
+                }
+            });
+            let accessor = parse_quote! {
+                impl #wrapper_ident {
+                    #[doc(hidden)]
+                    pub fn __mocktopus_get__() -> ::std::option::Option<&'static #ty> {
+                        ::std::option::Option::None
+                    }
+                }
+            };
+            let access_methods = quote! {
+                impl #wrapper_ident {
+                    /// Acquires a reference to the value in this thread local or to its mock
+                    #[allow(dead_code)]
+                    pub fn with<F, R>(&'static self, f: F) -> R where F: ::std::ops::FnOnce(&#ty) -> R {
+                        match #wrapper_ident::__mocktopus_get__() {
+                            ::std::option::Option::Some(value) => f(value),
+                            ::std::option::Option::None => #inner_ident.with(f),
+                        }
+                    }
+
+                    /// Acquires a reference to the value in this thread local or to its mock,
+                    /// fails if the thread local was destroyed
+                    #[allow(dead_code)]
+                    pub fn try_with<F, R>(&'static self, f: F) -> ::std::result::Result<R, ::std::thread::AccessError>
+                            where F: ::std::ops::FnOnce(&#ty) -> R {
+                        match #wrapper_ident::__mocktopus_get__() {
+                            ::std::option::Option::Some(value) => ::std::result::Result::Ok(f(value)),
+                            ::std::option::Option::None => #inner_ident.try_with(f),
+                        }
+                    }
+                }
+            };
+            (accessor, access_methods)
+        },
+    };
+    item_injector::inject_item(&mut accessor, config);
+    let accessor = accessor.into_token_stream();
+    let mock_result = match *kind {
+        StaticKind::ThreadLocal(_) => quote!(::std::option::Option::Some(value)),
+        _ => quote!(value),
+    };
+    let import_crate = build_import_crate(config.crate_path());
+    let crate_name = Ident::new(MOCKTOPUS_CRATE_NAME, Span::call_site());
+    let mut output = quote!(#(#items)*);
+    output.extend(quote! {
+        #cfg_attrs
+        const _: () = {
+            #import_crate
+
+            #accessor
+
+            #access_methods
+
+            impl #wrapper_ident {
+                /// Mocks value in current thread until returned guard is dropped, the mock value is leaked
+                #[allow(dead_code)]
+                pub fn mock_static(&self, value: #ty) -> #crate_name::mocking::MockGuard {
+                    let value: &'static #ty = ::std::boxed::Box::leak(::std::boxed::Box::new(value));
+                    #crate_name::mocking::Mockable::mock_scoped(&#wrapper_ident::__mocktopus_get__,
+                        move || #crate_name::mocking::MockResult::Return(#mock_result))
+                }
+            }
+        };
+    });
+    Ok(output)
+}
+
+fn is_attr_named(attr: &Attribute, names: &[&str]) -> bool {
+    attr.path.segments.len() == 1 && names.iter().any(|name| attr.path.segments[0].ident == name)
+}
+
+fn build_deref_impl(wrapper_ident: &Ident, ty: &Type) -> TokenStream {
+    quote! {
+        impl ::std::ops::Deref for #wrapper_ident {
+            type Target = #ty;
+
+            fn deref(&self) -> &#ty {
+                #wrapper_ident::__mocktopus_get__()
+            }
+        }
+    }
+}
+
+/// Statics have elided lifetimes implicitly `'static`, but generated functions and impls need them explicit
+fn make_elided_lifetimes_static(ty: &mut Type) {
+    match *ty {
+        Type::Reference(ref mut type_reference) => {
+            if type_reference.lifetime.is_none() {
+                type_reference.lifetime = Some(Lifetime::new("'static", type_reference.and_token.spans[0]));
+            }
+            make_elided_lifetimes_static(&mut type_reference.elem);
+        },
+        Type::Slice(ref mut type_slice)     => make_elided_lifetimes_static(&mut type_slice.elem),
+        Type::Array(ref mut type_array)     => make_elided_lifetimes_static(&mut type_array.elem),
+        Type::Ptr(ref mut type_ptr)         => make_elided_lifetimes_static(&mut type_ptr.elem),
+        Type::Paren(ref mut type_paren)     => make_elided_lifetimes_static(&mut type_paren.elem),
+        Type::Group(ref mut type_group)     => make_elided_lifetimes_static(&mut type_group.elem),
+        Type::Tuple(ref mut type_tuple)     => type_tuple.elems.iter_mut().for_each(make_elided_lifetimes_static),
+        Type::Path(ref mut type_path)       => {
+            for segment in &mut type_path.path.segments {
+                if let PathArguments::AngleBracketed(AngleBracketedGenericArguments { ref mut args, .. }) =
+                        segment.arguments {
+                    args.iter_mut()
+                        .for_each(|arg| match *arg {
+                            GenericArgument::Type(ref mut ty) => make_elided_lifetimes_static(ty),
+                            GenericArgument::Lifetime(ref mut lifetime) if lifetime.ident == "_" =>
+                                *lifetime = Lifetime::new("'static", lifetime.apostrophe),
+                            _ => (),
+                        });
+                }
+            }
+        },
+        _ => (),
+    }
+}
@@ -0,0 +1,102 @@
+use crate::header_builder::MOCKTOPUS_CRATE_NAME;
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use syn::{Expr, ItemFn, Path, Token};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+
+const MOCK_GUARDS_NAME: &str = "__mocktopus_mock_guards__";
+const TEST_BODY_NAME:   &str = "__mocktopus_test_body__";
+
+pub fn build_test(item_fn: &ItemFn, is_strict: bool) -> Result<TokenStream, String> {
+    check_test_fn(item_fn)?;
+    let ItemFn { ref attrs, ref vis, ref ident, ref decl, ref block, .. } = *item_fn;
+    let output = &decl.output;
+    // Mocks must be set inside of test body, after they are cleared
+    let (body_attrs, attrs): (Vec<_>, Vec<_>) = attrs.iter()
+        .partition(|attr| attr.path.segments.last().is_some_and(|segment| segment.value().ident == "with_mocks"));
+    let crate_name = Ident::new(MOCKTOPUS_CRATE_NAME, Span::call_site());
+    let body_ident = Ident::new(TEST_BODY_NAME, Span::call_site());
+    let run_body = match is_strict {
+        false => quote!(#body_ident),
+        true => quote!(|| {
+            let result = #body_ident();
+            #crate_name::mocking::verify_mocks_called();
+            result
+        }),
+    };
+    Ok(quote! {
+        #[test]
+        #(#attrs)*
+        #vis fn #ident() #output {
+            extern crate mocktopus as #crate_name;
+            #(#body_attrs)*
+            fn #body_ident() #output #block
+            #crate_name::mocking::run_test(#run_body)
+        }
+    })
+}
+
+/// `path::to::function => closure or value`
+struct TestMock {
+    path: Path,
+    mock: Expr,
+}
+
+impl Parse for TestMock {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let path = input.parse()?;
+        input.parse::<Token![=>]>()?;
+        let mock = input.parse()?;
+        Ok(TestMock { path, mock })
+    }
+}
+
+/// Arguments of `#[with_mocks]`, comma separated mocks
+pub struct TestMocks(Punctuated<TestMock, Token![,]>);
+
+impl Parse for TestMocks {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        input.parse_terminated(TestMock::parse).map(TestMocks)
+    }
+}
+
+pub fn build_test_with_mocks(test_mocks: &TestMocks, item_fn: &ItemFn) -> Result<TokenStream, String> {
+    check_test_fn(item_fn)?;
+    let ItemFn { ref attrs, ref vis, ref ident, ref decl, ref block, .. } = *item_fn;
+    let output = &decl.output;
+    let crate_name = Ident::new(MOCKTOPUS_CRATE_NAME, Span::call_site());
+    let guards_ident = Ident::new(MOCK_GUARDS_NAME, Span::call_site());
+    let guards = test_mocks.0.iter().map(|test_mock| {
+        let TestMock { ref path, ref mock } = *test_mock;
+        match *mock {
+            Expr::Closure(_) => quote!(#crate_name::mocking::Mockable::mock_scoped(&#path, #mock)),
+            _ => quote! {{
+                let value = #mock;
+                #crate_name::mocking::mock_tuple_scoped(&#path, move |_| #crate_name::mocking::MockResult::Return(
+                    ::std::clone::Clone::clone(&value)))
+            }},
+        }
+    });
+    Ok(quote! {
+        #(#attrs)*
+        #vis fn #ident() #output {
+            extern crate mocktopus as #crate_name;
+            let #guards_ident: Vec<#crate_name::mocking::MockGuard> = vec![#(#guards),*];
+            #block
+        }
+    })
+}
+
+fn check_test_fn(item_fn: &ItemFn) -> Result<(), String> {
+    if !item_fn.decl.inputs.is_empty() {
+        return Err(format!("test function '{}' can't have arguments", item_fn.ident))
+    }
+    if !item_fn.decl.generics.params.is_empty() {
+        return Err(format!("test function '{}' can't be generic", item_fn.ident))
+    }
+    if item_fn.asyncness.is_some() || item_fn.constness.is_some() || item_fn.unsafety.is_some() {
+        return Err(format!("test function '{}' can't be const, unsafe or async", item_fn.ident))
+    }
+    Ok(())
+}
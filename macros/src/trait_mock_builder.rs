@@ -0,0 +1,153 @@
+use proc_macro2::Span;
+use quote::quote;
+use syn::parse_quote;
+use syn::{ArgCaptured, FnArg, GenericParam, Generics, Ident, ImplItem, ImplItemMethod, Item, ItemTrait, Pat,
+          PatIdent, TraitItem, TraitItemMethod, Visibility};
+use syn::punctuated::Punctuated;
+use syn::token::Comma;
+
+pub fn build_trait_mock(item_trait: &ItemTrait) -> Result<Vec<Item>, String> {
+    let mock_ident = Ident::new(&format!("Mock{}", item_trait.ident), item_trait.ident.span());
+    let mut impl_items = vec![];
+    for trait_item in &item_trait.items {
+        match *trait_item {
+            TraitItem::Method(TraitItemMethod { default: Some(_), .. }) => (),
+            TraitItem::Method(ref method) => impl_items.push(build_method(&mock_ident, method)),
+            TraitItem::Const(ref item_const) if item_const.default.is_some() => (),
+            TraitItem::Const(ref item_const) =>
+                return Err(format!("associated const '{}' without default value is not supported", item_const.ident)),
+            TraitItem::Type(ref item_type) =>
+                return Err(format!("associated type '{}' is not supported", item_type.ident)),
+            _ => return Err("only methods and associated consts are supported".to_string()),
+        }
+    }
+    let struct_generics = build_struct_generics(&item_trait.generics)?;
+    let mut items = vec![build_struct(&item_trait.vis, &mock_ident, &struct_generics)];
+    items.extend(build_constructors(&mock_ident, &struct_generics));
+    items.push(build_trait_impl(item_trait, &mock_ident, &struct_generics, impl_items));
+    Ok(items)
+}
+
+fn build_struct_generics(trait_generics: &Generics) -> Result<Generics, String> {
+    let mut generics = Generics::default();
+    for param in &trait_generics.params {
+        let struct_param: GenericParam = match *param {
+            GenericParam::Lifetime(ref lifetime_def) => {
+                let lifetime = &lifetime_def.lifetime;
+                parse_quote!(#lifetime)
+            },
+            GenericParam::Type(ref type_param) => {
+                let ident = &type_param.ident;
+                parse_quote!(#ident: ?Sized)
+            },
+            GenericParam::Const(ref const_param) =>
+                return Err(format!("const generic parameter '{}' is not supported", const_param.ident)),
+        };
+        generics.params.push(struct_param);
+    }
+    Ok(generics)
+}
+
+fn build_struct(vis: &Visibility, mock_ident: &Ident, generics: &Generics) -> Item {
+    let doc = format!("Mock of trait `{}`, all its required methods are mockable and panic unless mocked",
+                      &mock_ident.to_string()["Mock".len()..]);
+    if generics.params.is_empty() {
+        return parse_quote! {
+            #[doc = #doc]
+            #[derive(Default)]
+            #vis struct #mock_ident;
+        }
+    }
+    let phantom_types = generics.params.iter()
+        .map(|param| match *param {
+            GenericParam::Lifetime(ref lifetime_def) => {
+                let lifetime = &lifetime_def.lifetime;
+                quote!(&#lifetime ())
+            },
+            GenericParam::Type(ref type_param) => {
+                let ident = &type_param.ident;
+                quote!(fn(&#ident))
+            },
+            GenericParam::Const(_) => unreachable!(),
+        });
+    parse_quote! {
+        #[doc = #doc]
+        #vis struct #mock_ident #generics (::std::marker::PhantomData<(#(#phantom_types,)*)>);
+    }
+}
+
+fn build_constructors(mock_ident: &Ident, generics: &Generics) -> Vec<Item> {
+    let (impl_generics, ty_generics, _) = generics.split_for_impl();
+    let new_impl = parse_quote! {
+        impl #impl_generics #mock_ident #ty_generics {
+            /// Creates new mock
+            #[allow(dead_code)]
+            pub fn new() -> Self {
+                ::std::default::Default::default()
+            }
+        }
+    };
+    if generics.params.is_empty() {
+        return vec![new_impl]
+    }
+    let default_impl = parse_quote! {
+        impl #impl_generics ::std::default::Default for #mock_ident #ty_generics {
+            fn default() -> Self {
+                #mock_ident(::std::marker::PhantomData)
+            }
+        }
+    };
+    vec![new_impl, default_impl]
+}
+
+fn build_trait_impl(item_trait: &ItemTrait, mock_ident: &Ident, struct_generics: &Generics,
+                    impl_items: Vec<ImplItem>) -> Item {
+    let unsafety = &item_trait.unsafety;
+    let trait_ident = &item_trait.ident;
+    let (impl_generics, trait_generics, where_clause) = item_trait.generics.split_for_impl();
+    let (_, mock_generics, _) = struct_generics.split_for_impl();
+    parse_quote! {
+        #[allow(unused_variables)]
+        #unsafety impl #impl_generics #trait_ident #trait_generics for #mock_ident #mock_generics #where_clause {
+            #(#impl_items)*
+        }
+    }
+}
+
+fn build_method(mock_ident: &Ident, method: &TraitItemMethod) -> ImplItem {
+    let mut sig = method.sig.clone();
+    name_fn_args(&mut sig.decl.inputs);
+    let panic_msg = format!("{}::{} was called, but it's not mocked", mock_ident, sig.ident);
+    let attrs = &method.attrs;
+    let impl_method: ImplItemMethod = parse_quote! {
+        #(#attrs)*
+        #sig {
+            panic!(#panic_msg)
+        }
+    };
+    ImplItem::Method(impl_method)
+}
+
+fn name_fn_args(inputs: &mut Punctuated<FnArg, Comma>) {
+    for (i, fn_arg) in inputs.iter_mut().enumerate() {
+        let arg_ident = Ident::new(&format!("__mocktopus_arg_{}__", i), Span::call_site());
+        let named_arg = match *fn_arg {
+            FnArg::Ignored(ref ty) => FnArg::Captured(
+                ArgCaptured {
+                    pat: Pat::Ident(
+                        PatIdent {
+                            by_ref: None,
+                            mutability: None,
+                            ident: arg_ident,
+                            subpat: None,
+                        }
+                    ),
+                    colon_token: Default::default(),
+                    ty: ty.clone(),
+                }
+            ),
+            _ => continue,
+        };
+        *fn_arg = named_arg;
+    }
+}
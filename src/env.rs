@@ -0,0 +1,28 @@
+use mocktopus_macros::mockable;
+use std::env::{self, VarError};
+use std::io;
+use std::path::PathBuf;
+
+/// Mockable wrapper of [env::var](https://doc.rust-lang.org/std/env/fn.var.html)
+#[mockable(mocktopus = "crate")]
+pub fn var(key: &str) -> Result<String, VarError> {
+    env::var(key)
+}
+
+/// Mockable wrapper of [env::vars](https://doc.rust-lang.org/std/env/fn.vars.html)
+#[mockable(mocktopus = "crate")]
+pub fn vars() -> Vec<(String, String)> {
+    env::vars().collect()
+}
+
+/// Mockable wrapper of [env::set_var](https://doc.rust-lang.org/std/env/fn.set_var.html)
+#[mockable(mocktopus = "crate")]
+pub fn set_var(key: &str, value: &str) {
+    env::set_var(key, value)
+}
+
+/// Mockable wrapper of [env::current_dir](https://doc.rust-lang.org/std/env/fn.current_dir.html)
+#[mockable(mocktopus = "crate")]
+pub fn current_dir() -> io::Result<PathBuf> {
+    env::current_dir()
+}
@@ -0,0 +1,170 @@
+use crate::mocking::{Mockable, MockGuard, MockResult};
+use crate::random::{SeedReporter, SeededRng};
+use std::any;
+use std::marker::{PhantomData, Tuple};
+
+/// Return type of function, which can be made to fail
+pub trait Fallible {
+    /// Value used to create a failure
+    type Error;
+
+    /// Creates a failure from an error value
+    fn from_error(error: Self::Error) -> Self;
+}
+
+impl<T, E> Fallible for Result<T, E> {
+    type Error = E;
+
+    fn from_error(error: E) -> Self {
+        Err(error)
+    }
+}
+
+impl<T> Fallible for Option<T> {
+    type Error = ();
+
+    fn from_error(_: ()) -> Self {
+        None
+    }
+}
+
+/// Trait for injecting failures into functions returning `Result` or `Option`
+///
+/// The trait is implemented for all functions returning [Fallible](trait.Fallible.html) types.
+/// Each method sets up a mock with [mock_safe](../mocking/trait.Mockable.html#tymethod.mock_safe) or, if it returns
+/// a guard, with [mock_scoped](../mocking/trait.Mockable.html#tymethod.mock_scoped), which on selected calls
+/// returns `Err` created by the `error` closure or `None`. Other calls run the real function body.
+/// Calls are counted from 1 since the failures were set up.
+///
+/// Note: methods have any effect only if called on functions [annotated as mockable](https://docs.rs/mocktopus_macros).
+pub trait MockableFaults<T: Tuple, O: Fallible> {
+    /// Makes only the `n`-th call fail
+    ///
+    /// ```
+    /// #[mockable]
+    /// fn read_config() -> io::Result<String> { ... }
+    ///
+    /// #[test]
+    /// fn read_config_retry_test() {
+    ///     read_config.fail_nth(1, || io::Error::new(io::ErrorKind::Interrupted, "mocked"));
+    ///
+    ///     assert!(read_config_with_retry().is_ok());
+    /// }
+    /// ```
+    fn fail_nth<E: FnMut() -> O::Error + 'static>(&self, n: usize, error: E);
+
+    /// Makes all calls after the first `n` ones fail
+    ///
+    /// ```
+    /// #[mockable]
+    /// fn next_value() -> Option<u32> { ... }
+    ///
+    /// #[test]
+    /// fn next_value_test() {
+    ///     next_value.fail_after(2, || ());
+    ///
+    ///     assert!(next_value().is_some());
+    ///     assert!(next_value().is_some());
+    ///     assert!(next_value().is_none());
+    /// }
+    /// ```
+    fn fail_after<E: FnMut() -> O::Error + 'static>(&self, n: usize, error: E);
+
+    /// Makes calls fail randomly with given probability until returned guard is dropped
+    ///
+    /// Failures are chosen by a pseudorandom generator initialized with `seed`, so for a given seed they always
+    /// happen on the same calls, which makes test failures reproducible. If the guard is dropped while the thread
+    /// is panicking, e.g. because an assertion failed, the seed is printed to stderr, so the failure can be replayed
+    /// by setting `MOCKTOPUS_SEED` when seed comes from [test_seed](../random/fn.test_seed.html).
+    ///
+    /// # Panics
+    /// When probability isn't in range from 0 to 1.
+    ///
+    /// ```
+    /// #[mockable]
+    /// fn send(message: &str) -> io::Result<()> { ... }
+    ///
+    /// #[test]
+    /// fn send_with_retry_test() {
+    ///     let _guard = send.fail_with_probability(0.1, random::test_seed(),
+    ///         || io::Error::new(io::ErrorKind::TimedOut, "mocked"));
+    ///
+    ///     for _ in 0..100 {
+    ///         assert!(send_with_retry("message").is_ok());
+    ///     }
+    /// }
+    /// ```
+    fn fail_with_probability<E: FnMut() -> O::Error + 'static>(&self, probability: f64, seed: u64, error: E)
+        -> MockGuard;
+}
+
+impl<T: Tuple, O: Fallible + 'static, F: FnOnce<T, Output=O>> MockableFaults<T, O> for F {
+    fn fail_nth<E: FnMut() -> O::Error + 'static>(&self, n: usize, error: E) {
+        self.mock_safe(FaultMock::new(FaultSchedule::Nth(n), error))
+    }
+
+    fn fail_after<E: FnMut() -> O::Error + 'static>(&self, n: usize, error: E) {
+        self.mock_safe(FaultMock::new(FaultSchedule::After(n), error))
+    }
+
+    fn fail_with_probability<E: FnMut() -> O::Error + 'static>(&self, probability: f64, seed: u64, error: E)
+            -> MockGuard {
+        assert!((0.0..=1.0).contains(&probability), "probability must be between 0 and 1, got {}", probability);
+        let reporter = SeedReporter::new(any::type_name::<F>(), seed, "failed calls chosen");
+        let schedule = FaultSchedule::Probability { probability, random: SeededRng::new(seed), _reporter: reporter };
+        self.mock_scoped(FaultMock::new(schedule, error))
+    }
+}
+
+enum FaultSchedule {
+    Nth(usize),
+    After(usize),
+    Probability { probability: f64, random: SeededRng, _reporter: SeedReporter },
+}
+
+impl FaultSchedule {
+    fn is_failing(&mut self, call_number: usize) -> bool {
+        match *self {
+            FaultSchedule::Nth(n) => call_number == n,
+            FaultSchedule::After(n) => call_number > n,
+            FaultSchedule::Probability { probability, ref mut random, .. } => random.next_f64() < probability,
+        }
+    }
+}
+
+/// Mock closure failing calls selected by schedule and continuing others
+struct FaultMock<O, E> {
+    schedule: FaultSchedule,
+    error: E,
+    call_count: usize,
+    _output: PhantomData<fn() -> O>,
+}
+
+impl<O, E> FaultMock<O, E> {
+    fn new(schedule: FaultSchedule, error: E) -> Self {
+        FaultMock {
+            schedule,
+            error,
+            call_count: 0,
+            _output: PhantomData,
+        }
+    }
+}
+
+impl<T: Tuple, O: Fallible, E: FnMut() -> O::Error> FnOnce<T> for FaultMock<O, E> {
+    type Output = MockResult<T, O>;
+
+    extern "rust-call" fn call_once(mut self, args: T) -> MockResult<T, O> {
+        self.call_mut(args)
+    }
+}
+
+impl<T: Tuple, O: Fallible, E: FnMut() -> O::Error> FnMut<T> for FaultMock<O, E> {
+    extern "rust-call" fn call_mut(&mut self, args: T) -> MockResult<T, O> {
+        self.call_count += 1;
+        match self.schedule.is_failing(self.call_count) {
+            true    => MockResult::Return(O::from_error((self.error)())),
+            false   => MockResult::Continue(args),
+        }
+    }
+}
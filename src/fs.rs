@@ -0,0 +1,344 @@
+//! Mockable, non-generic subset of [std::fs](https://doc.rust-lang.org/std/fs/index.html)
+//!
+//! This is not a drop-in replacement of `std::fs`. Functions take `&Path` instead of `P: AsRef<Path>`,
+//! so every call site goes through the same mockable function. Directory listing and metadata
+//! use plain data types, which mocks can create, so they have distinct names from their `std::fs` counterparts.
+
+use crate::mocking::{FakeMocks, Mockable, MockResult};
+use mocktopus_macros::mockable;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Mockable wrapper of [fs::read](https://doc.rust-lang.org/std/fs/fn.read.html) taking `&Path`
+#[mockable(mocktopus = "crate")]
+pub fn read(path: &Path) -> io::Result<Vec<u8>> {
+    fs::read(path)
+}
+
+/// Mockable wrapper of [fs::write](https://doc.rust-lang.org/std/fs/fn.write.html) taking `&Path` and `&[u8]`
+#[mockable(mocktopus = "crate")]
+pub fn write(path: &Path, contents: &[u8]) -> io::Result<()> {
+    fs::write(path, contents)
+}
+
+/// Lists directory like [fs::read_dir](https://doc.rust-lang.org/std/fs/fn.read_dir.html)
+///
+/// Returns paths of all entries of the directory in unspecified order instead of an iterator of `DirEntry`.
+#[mockable(mocktopus = "crate")]
+pub fn read_dir_paths(path: &Path) -> io::Result<Vec<PathBuf>> {
+    fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect()
+}
+
+/// Queries metadata like [fs::metadata](https://doc.rust-lang.org/std/fs/fn.metadata.html)
+///
+/// Returns [EntryMetadata](struct.EntryMetadata.html) instead of `fs::Metadata`, which can't be created by mocks.
+#[mockable(mocktopus = "crate")]
+pub fn entry_metadata(path: &Path) -> io::Result<EntryMetadata> {
+    fs::metadata(path).map(|metadata| EntryMetadata {
+        is_dir: metadata.is_dir(),
+        len: metadata.len(),
+    })
+}
+
+/// Mockable wrapper of [fs::create_dir_all](https://doc.rust-lang.org/std/fs/fn.create_dir_all.html) taking `&Path`
+#[mockable(mocktopus = "crate")]
+pub fn create_dir_all(path: &Path) -> io::Result<()> {
+    fs::create_dir_all(path)
+}
+
+/// Mockable wrapper of [fs::remove_file](https://doc.rust-lang.org/std/fs/fn.remove_file.html) taking `&Path`
+#[mockable(mocktopus = "crate")]
+pub fn remove_file(path: &Path) -> io::Result<()> {
+    fs::remove_file(path)
+}
+
+/// Subset of [fs::Metadata](https://doc.rust-lang.org/std/fs/struct.Metadata.html), which can be created by mocks
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EntryMetadata {
+    is_dir: bool,
+    len: u64,
+}
+
+#[allow(clippy::len_without_is_empty)]
+impl EntryMetadata {
+    /// Creates metadata of a file
+    pub fn file(len: u64) -> Self {
+        EntryMetadata { is_dir: false, len }
+    }
+
+    /// Creates metadata of a directory
+    pub fn dir() -> Self {
+        EntryMetadata { is_dir: true, len: 0 }
+    }
+
+    /// Returns true if metadata is of a directory
+    pub fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+
+    /// Returns true if metadata is of a file
+    pub fn is_file(&self) -> bool {
+        !self.is_dir
+    }
+
+    /// Size of the file in bytes
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+}
+
+/// File opened for reading, either a real one or with contents provided by a mock
+#[derive(Debug)]
+pub struct File(FileInner);
+
+#[derive(Debug)]
+enum FileInner {
+    Real(fs::File),
+    Memory(Cursor<Vec<u8>>),
+}
+
+#[mockable(mocktopus = "crate")]
+impl File {
+    /// Mockable wrapper of [File::open](https://doc.rust-lang.org/std/fs/struct.File.html#method.open) taking `&Path`
+    pub fn open(path: &Path) -> io::Result<File> {
+        fs::File::open(path).map(|file| File(FileInner::Real(file)))
+    }
+}
+
+impl File {
+    /// Creates file with given contents, which can be returned by mocks
+    pub fn from_contents(contents: Vec<u8>) -> Self {
+        File(FileInner::Memory(Cursor::new(contents)))
+    }
+}
+
+impl Read for File {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.0 {
+            FileInner::Real(ref mut file) => file.read(buf),
+            FileInner::Memory(ref mut cursor) => cursor.read(buf),
+        }
+    }
+}
+
+impl Seek for File {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self.0 {
+            FileInner::Real(ref mut file) => file.seek(pos),
+            FileInner::Memory(ref mut cursor) => cursor.seek(pos),
+        }
+    }
+}
+
+/// Filesystem fake, which replaces real filesystem in current thread
+///
+/// Creating fake mocks all functions of this module, so they operate on files and directories stored in memory.
+/// The fake starts empty, only the root and current directory are considered existing.
+/// Paths are compared as they are without resolving `.`, `..` or links.
+/// The files live as long as the fake, dropping it brings back the functions' previous behavior.
+///
+/// ```
+/// fn load_config(path: &Path) -> io::Result<String> {
+///     let bytes = fs::read(path)?;
+///     Ok(String::from_utf8_lossy(&bytes).into_owned())
+/// }
+///
+/// #[test]
+/// fn load_config_test() {
+///     let fs = InMemoryFs::new();
+///     fs.add_file("/etc/app.conf", "key = value");
+///     fs.fail_with("/etc/secret.conf", io::ErrorKind::PermissionDenied);
+///
+///     assert_eq!("key = value", load_config(Path::new("/etc/app.conf")).unwrap());
+///     assert_eq!(io::ErrorKind::PermissionDenied,
+///         load_config(Path::new("/etc/secret.conf")).unwrap_err().kind());
+/// }
+/// ```
+pub struct InMemoryFs {
+    state: Rc<RefCell<FsState>>,
+    _mocks: FakeMocks,
+}
+
+impl InMemoryFs {
+    /// Creates empty filesystem and mocks all functions of this module with it
+    pub fn new() -> Self {
+        let state = Rc::new(RefCell::new(FsState::default()));
+        let mocks = FakeMocks::new(vec![
+            read.mock_scoped({
+                let state = state.clone();
+                move |path| MockResult::Return(state.borrow().read(path))
+            }),
+            write.mock_scoped({
+                let state = state.clone();
+                move |path, contents| MockResult::Return(state.borrow_mut().write(path, contents))
+            }),
+            read_dir_paths.mock_scoped({
+                let state = state.clone();
+                move |path| MockResult::Return(state.borrow().read_dir(path))
+            }),
+            entry_metadata.mock_scoped({
+                let state = state.clone();
+                move |path| MockResult::Return(state.borrow().metadata(path))
+            }),
+            create_dir_all.mock_scoped({
+                let state = state.clone();
+                move |path| MockResult::Return(state.borrow_mut().create_dir_all(path))
+            }),
+            remove_file.mock_scoped({
+                let state = state.clone();
+                move |path| MockResult::Return(state.borrow_mut().remove_file(path))
+            }),
+            File::open.mock_scoped({
+                let state = state.clone();
+                move |path| MockResult::Return(state.borrow().read(path).map(File::from_contents))
+            }),
+        ]);
+        InMemoryFs {
+            state,
+            _mocks: mocks,
+        }
+    }
+
+    /// Adds file with given contents replacing existing one, creates all missing parent directories
+    pub fn add_file<P: AsRef<Path>, C: Into<Vec<u8>>>(&self, path: P, contents: C) {
+        let mut state = self.state.borrow_mut();
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            state.add_dirs(parent);
+        }
+        state.files.insert(path.to_path_buf(), contents.into());
+    }
+
+    /// Adds directory and all missing parent directories
+    pub fn add_dir<P: AsRef<Path>>(&self, path: P) {
+        self.state.borrow_mut().add_dirs(path.as_ref());
+    }
+
+    /// Returns contents of file, if it exists
+    pub fn file<P: AsRef<Path>>(&self, path: P) -> Option<Vec<u8>> {
+        self.state.borrow().files.get(path.as_ref()).cloned()
+    }
+
+    /// Returns true if directory exists
+    pub fn has_dir<P: AsRef<Path>>(&self, path: P) -> bool {
+        self.state.borrow().is_dir(path.as_ref())
+    }
+
+    /// Makes all operations on given path fail with error of given kind
+    pub fn fail_with<P: AsRef<Path>>(&self, path: P, kind: io::ErrorKind) {
+        self.state.borrow_mut().errors.insert(path.as_ref().to_path_buf(), kind);
+    }
+}
+
+impl Default for InMemoryFs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Default)]
+struct FsState {
+    files: BTreeMap<PathBuf, Vec<u8>>,
+    dirs: BTreeSet<PathBuf>,
+    errors: HashMap<PathBuf, io::ErrorKind>,
+}
+
+impl FsState {
+    fn check_error(&self, path: &Path) -> io::Result<()> {
+        match self.errors.get(path) {
+            Some(&kind) => Err(io::Error::new(kind, format!("injected error for {}", path.display()))),
+            None => Ok(()),
+        }
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        is_root(path) || self.dirs.contains(path)
+    }
+
+    fn add_dirs(&mut self, path: &Path) {
+        for ancestor in path.ancestors().filter(|ancestor| !is_root(ancestor)) {
+            self.dirs.insert(ancestor.to_path_buf());
+        }
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.check_error(path)?;
+        match self.files.get(path) {
+            Some(contents) => Ok(contents.clone()),
+            None if self.is_dir(path) => Err(error(io::ErrorKind::Other, "is a directory", path)),
+            None => Err(error(io::ErrorKind::NotFound, "not found", path)),
+        }
+    }
+
+    fn write(&mut self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        self.check_error(path)?;
+        if self.is_dir(path) {
+            return Err(error(io::ErrorKind::Other, "is a directory", path))
+        }
+        if let Some(parent) = path.parent() {
+            if !self.is_dir(parent) {
+                return Err(error(io::ErrorKind::NotFound, "parent directory not found", path))
+            }
+        }
+        self.files.insert(path.to_path_buf(), contents.to_vec());
+        Ok(())
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        self.check_error(path)?;
+        if !self.is_dir(path) {
+            let kind = match self.files.contains_key(path) {
+                true    => io::ErrorKind::Other,
+                false   => io::ErrorKind::NotFound,
+            };
+            return Err(error(kind, "not a directory", path))
+        }
+        Ok(self.dirs.iter()
+            .chain(self.files.keys())
+            .filter(|entry| entry.parent() == Some(path))
+            .cloned()
+            .collect())
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<EntryMetadata> {
+        self.check_error(path)?;
+        match self.files.get(path) {
+            Some(contents) => Ok(EntryMetadata::file(contents.len() as u64)),
+            None if self.is_dir(path) => Ok(EntryMetadata::dir()),
+            None => Err(error(io::ErrorKind::NotFound, "not found", path)),
+        }
+    }
+
+    fn create_dir_all(&mut self, path: &Path) -> io::Result<()> {
+        self.check_error(path)?;
+        if let Some(file) = path.ancestors().find(|ancestor| self.files.contains_key(*ancestor)) {
+            return Err(error(io::ErrorKind::AlreadyExists, "file exists", file))
+        }
+        self.add_dirs(path);
+        Ok(())
+    }
+
+    fn remove_file(&mut self, path: &Path) -> io::Result<()> {
+        self.check_error(path)?;
+        match self.files.remove(path) {
+            Some(_) => Ok(()),
+            None if self.is_dir(path) => Err(error(io::ErrorKind::Other, "is a directory", path)),
+            None => Err(error(io::ErrorKind::NotFound, "not found", path)),
+        }
+    }
+}
+
+/// Root and empty path, which is parent of relative paths with a single component, always exist
+fn is_root(path: &Path) -> bool {
+    path.parent().is_none() || path.as_os_str().is_empty()
+}
+
+fn error(kind: io::ErrorKind, message: &str, path: &Path) -> io::Error {
+    io::Error::new(kind, format!("{}: {}", message, path.display()))
+}
@@ -1,4 +1,6 @@
-#![feature(fn_traits, unboxed_closures)]
+#![feature(tuple_trait, fn_traits, unboxed_closures)]
+// Doc examples show tests, they are never run as doctests
+#![allow(clippy::test_attr_in_doctest)]
 
 //! Mocking framework for Rust (currently only nightly)
 //!
@@ -33,7 +35,7 @@
 //!
 //! ```
 //! [dev-dependencies]
-//! mocktopus = "0.7.0"
+//! mocktopus = "0.8.0"
 //! ```
 //! Enable procedural macros in crate root:
 //!
@@ -71,7 +73,7 @@
 //!
 //! ```
 //! impl Struct {
-//!     #[mockable] // WRONG, will break Mocktopus
+//!     #[mockable] // WRONG, fails to compile
 //!     fn my_method() {}
 //! }
 //! ```
@@ -86,9 +88,47 @@
 //! This does NOT work for modules in separate file:
 //!
 //! ```
-//! #[cfg_attr(test, mockable)] // WRONG, has no effect
+//! #[cfg_attr(test, mockable)] // WRONG, has no effect and raises warning
 //! mod my_module;
 //! ```
+//! Annotation arguments can limit, which functions become mockable:
+//!
+//! ```
+//! #[cfg_attr(test, mockable(except(my_fn_2)))]
+//! mod my_module {
+//!     fn my_fn_1() {}
+//!     fn my_fn_2() {} // not mockable
+//! }
+//! ```
+//! Extern blocks annotated with `safe` get safe mockable shims of their functions, which can be even used
+//! without linking. It should be used only with functions, which are safe to call with any arguments:
+//!
+//! ```
+//! #[cfg_attr(test, mockable(safe, unlinked))]
+//! extern "C" {
+//!     fn c_function(x: i32) -> i32;
+//! }
+//! ```
+//! Functions from external crates can't be annotated, but they can be replaced with mockable wrappers:
+//!
+//! ```
+//! mocktopus::mock_extern! {
+//!     pub mod fs = std::fs {
+//!         fn read_to_string(path: &str) -> std::io::Result<String>;
+//!     }
+//! }
+//!
+//! #[cfg(test)]
+//! use self::fs;
+//! #[cfg(not(test))]
+//! use std::fs;
+//! ```
+//! Statics can be made mockable too, then they can be mocked in tests with `mock_static`:
+//!
+//! ```
+//! #[cfg_attr(test, mockable_static)]
+//! static MY_STATIC: u32 = 1;
+//! ```
 //! # Mocking
 //! Import tools for mocking in test module:
 //!
@@ -118,6 +158,31 @@
 //! // Mocking default trait method
 //! MyStruct::my_trait_default_method.mock_safe(|| MockResult::Return(3));
 //! ```
+//! Code using trait objects or generics can be tested with mocks generated for whole traits:
+//!
+//! ```
+//! #[cfg_attr(test, mockable_trait)]
+//! trait MyTrait {
+//!     fn my_trait_method(&self) -> u32;
+//! }
+//!
+//! // Generated struct implementing MyTrait
+//! MockMyTrait::my_trait_method.mock_safe(|_| MockResult::Return(4));
+//! let my_trait: Box<dyn MyTrait> = Box::new(MockMyTrait::new());
+//! ```
+//! Trait methods can be also mocked at once for all implementors from mockable impls or trait defaults:
+//!
+//! ```
+//! #[macro_use]
+//! extern crate mocktopus;
+//!
+//! mock_trait_method!(MyTrait::my_trait_method, |this: TraitImplementor| MockResult::Return(5));
+//! ```
+//! Destructors from mockable `Drop` impls can be mocked too, but they have a dedicated interface:
+//!
+//! ```
+//! mock_drop(|my_struct: &mut MyStruct| MockResult::Return(()));
+//! ```
 //! Mocking with `mock_safe` is simplest, but the `Mockable` trait has more,
 //! see [documantation](mocking/trait.Mockable.html).
 //!
@@ -150,6 +215,24 @@
 //! }
 //! ```
 //!
+//! When tests are run on reused threads, e.g. by a custom harness, they can be annotated with
+//! `#[mocktopus::mocktopus_test]` instead of `#[test]`. Such tests start and end with no mocks set
+//! and they fail if any expectation set with [mock_expect](mocking/trait.Mockable.html#tymethod.mock_expect)
+//! was not met:
+//!
+//! ```
+//! #[mocktopus::mocktopus_test]
+//! fn common_fn_test_3() {
+//!     common_fn.mock_expect(1, || MockResult::Return(3));
+//!
+//!     assert_eq!(3, common_fn());
+//! }
+//! ```
+//!
+//! Setting up a mock of a function, which isn't mockable, panics, so forgotten annotations don't pass silently.
+//! Generic functions and trait methods aren't checked, but tests annotated with
+//! `#[mocktopus::mocktopus_test(strict)]` fail if any mock was never called, which catches them too.
+//!
 //! ## Mock closure
 //! `mock_safe` has single argument: a closure, which takes same input as mocked function and returns a `MockResult`.
 //! Whenever the mocked function is called, its inputs are passed to the closure:
@@ -203,6 +286,100 @@
 //!     assert_eq!(3, my_function_3(1, 1));
 //! }
 //! ```
+//! To simulate failures the closure can return `MockResult::Panic` with a message or `MockResult::Unwind`
+//! with any payload. The mocked function panics itself, panic messages name the function and the number
+//! of the mock call, which failed:
+//!
+//! ```
+//! #[cfg_attr(test, mockable)]
+//! fn my_function_4() -> u32 {
+//!     1
+//! }
+//!
+//! #[test]
+//! #[should_panic(expected = "my_function_4 panicked on call 1: mocked failure")]
+//! fn my_function_4_test() {
+//!     my_function_4.mock_safe(|| MockResult::Panic("mocked failure".to_string()));
+//!
+//!     my_function_4();
+//! }
+//! ```
+//! Functions returning `Result` or `Option` can have failures injected on selected calls
+//! with [faults](faults/trait.MockableFaults.html), other calls run normally:
+//!
+//! ```
+//! #[cfg_attr(test, mockable)]
+//! fn my_function_5(x: u32) -> Result<u32, String> {
+//!     Ok(x)
+//! }
+//!
+//! #[test]
+//! fn my_function_5_test() {
+//!     my_function_5.fail_nth(2, || "mocked".to_string());
+//!
+//!     assert_eq!(Ok(1), my_function_5(1));
+//!     assert_eq!(Err("mocked".to_string()), my_function_5(2));
+//!     assert_eq!(Ok(3), my_function_5(3));
+//! }
+//! ```
+//!
+//! ## Mock rules
+//! Common mocks can be declared with [mock!](macros/macro.mock.html) rules matching function arguments
+//! with patterns. For each function the first matching rule returns a value, continues or panics:
+//!
+//! ```
+//! #[cfg_attr(test, mockable)]
+//! fn my_function_6(x: u32) -> u32 {
+//!     x
+//! }
+//!
+//! #[test]
+//! fn my_function_6_test() {
+//!     mock! {
+//!         my_function_6(1) => 10,
+//!         my_function_6(x) if x > 100 => panic("too big: {}", x),
+//!         my_function_6(_) => continue,
+//!     }
+//!
+//!     assert_eq!(10, my_function_6(1));
+//!     assert_eq!(2, my_function_6(2));
+//! }
+//! ```
+//!
+//! Mocks used during whole test can be listed in [#\[with_mocks\]](macros/attr.with_mocks.html) attribute.
+//! Closures are used as mock closures, other values are returned from every call:
+//!
+//! ```
+//! #[test]
+//! #[with_mocks(my_function_6 => 10)]
+//! fn my_function_6_with_mocks_test() {
+//!     assert_eq!(10, my_function_6(1));
+//! }
+//! ```
+//!
+//! ## Typed mock helpers
+//! Every mockable free function `name` gets a companion `__mock_name` with typed helpers.
+//! Their closures receive arguments as a struct with fields named like the function arguments
+//! and return plain values, so type errors point at the function signature:
+//!
+//! ```
+//! #[cfg_attr(test, mockable)]
+//! fn my_function_7(name: &str, times: u32) -> String {
+//!     name.repeat(times as usize)
+//! }
+//!
+//! #[test]
+//! fn my_function_7_test() {
+//!     __mock_my_function_7::calls(|args| format!("{} x{}", args.name, args.times));
+//!
+//!     assert_eq!("a x2", my_function_7("a", 2));
+//! }
+//! ```
+//!
+//! There are also `returns(value)` returning clone of the value on every call and `expect(times, closure)`,
+//! which is typed [mock_expect](mocking/trait.Mockable.html#tymethod.mock_expect).
+//! Companions aren't generated for methods and for functions, which have generics, named lifetimes,
+//! or `impl Trait` in signature. They are hidden and never visible outside of the crate.
 //!
 //! ## Mocking generics
 //! When mocking generic functions, all its generics must be defined and only this variant will be affected:
@@ -236,6 +413,21 @@
 //!     assert_eq!("mocked", lifetime_generic_fn(&"not mocked".to_string()));
 //! }
 //! ```
+//! Types of `impl Trait` arguments can't be named, they're inferred from the mock closure arguments:
+//!
+//! ```
+//! #[cfg_attr(test, mockable)]
+//! fn impl_trait_fn<T: Display>(t: T, u: impl Display) -> String {
+//!     format!("{} {}", t, u)
+//! }
+//!
+//! #[test]
+//! fn impl_trait_fn_test() {
+//!     impl_trait_fn::<u32>.mock_safe(|_, _: &str| MockResult::Return("mocked".to_string()));
+//!
+//!     assert_eq!("mocked", impl_trait_fn(1u32, "a"));
+//! }
+//! ```
 //! Same rules apply to methods and structures:
 //!
 //! ```
@@ -282,6 +474,27 @@
 //! process termination, so it's viable solution only for use in tests and only if structure doesn't block a lot of
 //! resources like huge amounts of memory, open file handlers, sockets, etc.
 //!
+//! ## Returning reference borrowed from argument
+//!
+//! ```
+//! #[mockable]
+//! fn my_fn(my_string: &String) -> &str {
+//!     my_string
+//! }
+//!
+//! #[test]
+//! fn my_fn_test() {
+//!     my_fn.mock_ref(|my_string: &String| MockResult::Return(&my_string[4..]));
+//!
+//!     assert_eq!("mocked", my_fn(&"not mocked".to_string()));
+//! }
+//! ```
+//! Functions taking a single reference and returning a reference with the same lifetime can be mocked with
+//! [mock_ref](mocking/trait.MockableRef.html#tymethod.mock_ref). It accepts closures valid for any lifetime
+//! of the argument, so they can return data borrowed from it without leaking.
+//! Functions returning mutable references borrowed from a mutable reference argument can be mocked the same way
+//! with [mock_mut](mocking/trait.MockableMut.html#tymethod.mock_mut).
+//!
 //! ## Returning value created outside of mock
 //!
 //! ```
@@ -316,20 +529,163 @@
 //! }
 //! ```
 //! The vector can store `MockResult`s for more complex mocking.
+//!
+//! ## Controlling time
+//!
+//! ```
+//! fn wait_for_ready() -> bool {
+//!     let start = time::instant_now();
+//!     while !is_ready() {
+//!         if time::instant_now() - start > Duration::from_secs(10) {
+//!             return false;
+//!         }
+//!         time::sleep(Duration::from_secs(1));
+//!     }
+//!     true
+//! }
+//!
+//! #[test]
+//! fn wait_for_ready_test() {
+//!     let clock = FakeClock::new();
+//!     is_ready.mock_safe(|| MockResult::Return(false));
+//!
+//!     assert!(!wait_for_ready());
+//!     assert_eq!(Duration::from_secs(11), clock.elapsed());
+//! }
+//! ```
+//! Code using [time](time/index.html) wrappers instead of `Instant::now`, `SystemTime::now` and `thread::sleep`
+//! can be tested with a [FakeClock](time/struct.FakeClock.html). Sleeping returns immediately and only moves
+//! the fake clock forward, so tests are fast and deterministic.
+//!
+//! ## Faking filesystem
+//!
+//! Code using [fs](fs/index.html) wrappers instead of `std::fs` can be tested with an
+//! [InMemoryFs](fs/struct.InMemoryFs.html) without creating temporary directories.
+//! It can also make operations on selected paths fail:
+//!
+//! ```
+//! #[test]
+//! fn load_config_test() {
+//!     let fs = InMemoryFs::new();
+//!     fs.add_file("/etc/app.conf", "key = value");
+//!     fs.fail_with("/etc/secret.conf", io::ErrorKind::PermissionDenied);
+//!
+//!     assert!(load_config(Path::new("/etc/app.conf")).is_ok());
+//!     assert!(load_config(Path::new("/etc/secret.conf")).is_err());
+//! }
+//! ```
+//!
+//! ## Intercepting process exit
+//!
+//! Code calling [process::exit](process/fn.exit.html) instead of `std::process::exit` can be tested
+//! with [catch_exit](process/fn.catch_exit.html), which turns exit into an unwind and returns the exit code:
+//!
+//! ```
+//! fn run(args: &[&str]) {
+//!     if args.is_empty() {
+//!         process::exit(2);
+//!     }
+//!     ...
+//! }
+//!
+//! #[test]
+//! fn run_test() {
+//!     assert_eq!(Err(2), catch_exit(|| run(&[])));
+//! }
+//! ```
+//! Environment can be mocked the same way with [env](env/index.html) wrappers.
+//!
+//! ## Scripting commands
+//!
+//! Code running commands with [process::Command](process/struct.Command.html) facade can be tested with
+//! a [CommandScript](process/struct.CommandScript.html), so no real programs are needed:
+//!
+//! ```
+//! #[test]
+//! fn current_commit_test() {
+//!     let script = CommandScript::new();
+//!     script.on("git rev-parse HEAD").stdout("abc123\n").exit_code(0);
+//!
+//!     assert_eq!("abc123", current_commit().unwrap());
+//!     assert_eq!(vec!["git rev-parse HEAD"], script.executed());
+//! }
+//! ```
+//!
+//! ## Faking network
+//!
+//! Protocol clients using [net::TcpStream](net/struct.TcpStream.html) and [net::UdpSocket](net/struct.UdpSocket.html)
+//! facades can talk to peers scripted with [LoopbackNet](net/struct.LoopbackNet.html), so no real sockets are opened:
+//!
+//! ```
+//! #[test]
+//! fn fetch_status_test() {
+//!     let net = LoopbackNet::new();
+//!     let server = net.tcp_peer("status.example.com:80").send("OK\n").reset_after_write(100);
+//!
+//!     assert_eq!("OK", fetch_status("status.example.com:80").unwrap());
+//!     assert_eq!(b"STATUS\n".to_vec(), server.received());
+//! }
+//! ```
+//!
+//! ## Making randomness deterministic
+//!
+//! Random number sources like [random::random_f64](random/fn.random_f64.html) or functions of tested code
+//! designated as such can be made to return fixed sequences or values of a seeded generator with
+//! [MockableRandom](random/trait.MockableRandom.html). When a test fails, the seed is printed, so the run can be
+//! replayed by setting `MOCKTOPUS_SEED`:
+//!
+//! ```
+//! #[test]
+//! fn jitter_test() {
+//!     let _seed = random::random_f64.mock_seeded(random::test_seed());
+//!
+//!     assert!(jitter(Duration::from_secs(1)) < Duration::from_secs(2));
+//! }
+//! ```
 #![doc(html_logo_url = "https://raw.githubusercontent.com/CodeSandwich/mocktopus/master/logo.png",
     html_favicon_url = "https://raw.githubusercontent.com/CodeSandwich/mocktopus/master/logo.png")]
 
 extern crate mocktopus_macros;
 
+/// For use in tested code: mockable environment functions
+pub mod env;
+
+/// For use in testing code: injecting failures into functions returning `Result` or `Option`
+pub mod faults;
+
+/// For use in tested and testing code: mockable filesystem functions and an in-memory filesystem
+pub mod fs;
+
 /// For use in testing code: mocking tools
 pub mod mocking;
 
 /// For use in testing code: helper tools for writing tests using mocking
 pub mod mocking_utils;
 
+/// For use in tested and testing code: mockable network connections and an in-process network fake
+pub mod net;
+
+/// For use in tested and testing code: mockable process functions and commands with a scriptable fake
+pub mod process;
+
+/// For use in tested and testing code: mockable random number sources and deterministic generators
+pub mod random;
+
+/// For use in tested and testing code: mockable time functions and a clock controlled by tests
+pub mod time;
+
 /// For use in tested code: tools making items mockable
 pub mod macros {
     pub use mocktopus_macros::*;
 }
 
+/// For use in tested code: generates mockable wrappers of external modules
+pub use mocktopus_macros::mock_extern;
+
+/// For use in testing code: sets up mocks declared with rules matching arguments
+pub use mocktopus_macros::mock;
+
+/// For use in testing code: marks test running with clean mocks and verified expectations
+pub use mocktopus_macros::mocktopus_test;
+
 
@@ -1,15 +1,24 @@
-use std::any::{Any, TypeId};
-use std::cell::RefCell;
-use std::collections::HashMap;
+use std::any::{self, Any, TypeId};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Display;
+use std::hash::Hash;
+use std::marker::{PhantomData, Tuple};
 use std::mem::transmute;
+use std::panic;
 use std::rc::Rc;
+use std::sync::{Mutex, PoisonError};
+use std::thread::LocalKey;
 
 /// Trait for setting up mocks
 ///
 /// The trait is implemented for all functions, so its methods can be called on any function.
 ///
 /// Note: methods have any effect only if called on functions [annotated as mockable](https://docs.rs/mocktopus_macros).
-pub trait Mockable<T, O> {
+/// Setting up a mock of other function panics, unless it's generic or it's a trait method, e.g. `generic::<u32>` or
+/// `<Struct as Trait>::method`. Those aren't checked, but mocks, which are never called, can be detected with
+/// [verify_mocks_called](fn.verify_mocks_called.html).
+pub trait Mockable<T: Tuple, O> {
 
     /// Core function for setting up mocks
     ///
@@ -64,6 +73,50 @@ pub trait Mockable<T, O> {
     /// ```
     fn mock_safe<M: FnMut<T, Output=MockResult<T, O>> + 'static>(&self, mock: M);
 
+    /// A variant of [mock_safe](#tymethod.mock_safe), which sets mock until returned guard is dropped
+    ///
+    /// Dropping the guard restores mock, which was set before calling this method
+    /// or removes the mock if there was none.
+    ///
+    /// ```
+    /// #[mockable]
+    /// fn get_string() -> String {
+    ///     "not mocked".to_string()
+    /// }
+    ///
+    /// #[test]
+    /// fn get_string_test() {
+    ///     {
+    ///         let _guard = get_string.mock_scoped(|| MockResult::Return("mocked".to_string()));
+    ///
+    ///         assert_eq!("mocked", get_string());
+    ///     }
+    ///
+    ///     assert_eq!("not mocked", get_string());
+    /// }
+    /// ```
+    fn mock_scoped<M: FnMut<T, Output=MockResult<T, O>> + 'static>(&self, mock: M) -> MockGuard;
+
+    /// A variant of [mock_safe](#tymethod.mock_safe), which expects the mock to be called exactly `times` times
+    ///
+    /// The expectation stays pending until [verify_expectations](fn.verify_expectations.html) is called,
+    /// which is done automatically after tests annotated with
+    /// [#\[mocktopus::mocktopus_test\]](https://docs.rs/mocktopus_macros/*/mocktopus_macros/attr.mocktopus_test.html).
+    /// Only calls of this mock are counted, so if it's replaced, later calls don't count.
+    ///
+    /// ```
+    /// #[mockable]
+    /// fn send(message: &str) { ... }
+    ///
+    /// #[mocktopus::mocktopus_test]
+    /// fn broadcast_test() {
+    ///     send.mock_expect(2, |_| MockResult::Return(()));
+    ///
+    ///     broadcast(&["first", "second"]);
+    /// }
+    /// ```
+    fn mock_expect<M: FnMut<T, Output=MockResult<T, O>> + 'static>(&self, times: usize, mock: M);
+
     #[doc(hidden)]
     /// Called before every execution of a mockable function. Checks if mock is set and if it is, calls it.
     fn call_mock(&self, input: T) -> MockResult<T, O>;
@@ -74,6 +127,10 @@ pub trait Mockable<T, O> {
 }
 
 /// Controls mocked function behavior when returned from [mock closure](trait.Mockable.html)
+///
+/// The enum is non-exhaustive, so matching it outside of Mocktopus requires a wildcard arm.
+/// This allows adding new ways of controlling mocked functions without breaking code using them.
+#[non_exhaustive]
 pub enum MockResult<T, O> {
     /// Function runs normally as if it was called with given arguments.
     /// The arguments are passed inside enum variant as a tuple.
@@ -81,28 +138,275 @@ pub enum MockResult<T, O> {
 
     /// Function returns immediately with a given value. The returned value is passed inside enum variant.
     Return(O),
+
+    /// Function panics with a given message. The panic is raised by the mocked function itself,
+    /// the message is prefixed with the function name and the number of the mock call, which failed.
+    Panic(String),
+
+    /// Function panics with a given payload without changing it or running the panic hook,
+    /// like [resume_unwind](https://doc.rust-lang.org/std/panic/fn.resume_unwind.html).
+    /// The payload can be matched after catching the panic with
+    /// [catch_unwind](https://doc.rust-lang.org/std/panic/fn.catch_unwind.html).
+    Unwind(Box<dyn Any + Send>),
+}
+
+impl<T, O> MockResult<T, O> {
+    fn with_call_info<N: Display>(self, fn_name: N, call_number: usize) -> Self {
+        match self {
+            MockResult::Panic(message) =>
+                MockResult::Panic(format!("mock of {} panicked on call {}: {}", fn_name, call_number, message)),
+            mock_result => mock_result,
+        }
+    }
+
+    #[doc(hidden)]
+    /// Called by mockable functions, when mock result makes them neither continue nor return.
+    /// The panic location is the mockable function, which called it.
+    #[track_caller]
+    pub fn resume_panic(self) -> ! {
+        match self {
+            MockResult::Panic(message) => panic!("{}", message),
+            MockResult::Unwind(payload) => panic::resume_unwind(payload),
+            MockResult::Continue(_) | MockResult::Return(_) =>
+                panic!("Mocktopus internal error: mock result resumed as panic neither panics nor unwinds"),
+        }
+    }
+
+    #[doc(hidden)]
+    /// Called by mockable trait methods to pass results of trait mocks, which make them neither continue nor return
+    #[track_caller]
+    pub fn into_failure<U, P>(self) -> MockResult<U, P> {
+        match self {
+            MockResult::Panic(message) => MockResult::Panic(message),
+            MockResult::Unwind(payload) => MockResult::Unwind(payload),
+            MockResult::Continue(_) | MockResult::Return(_) =>
+                panic!("Mocktopus internal error: mock result converted to failure neither panics nor unwinds"),
+        }
+    }
 }
 
+type StoredMock = Rc<RefCell<Box<dyn FnMut<(), Output=()>>>>;
+
+/// Mock of `Drop::drop` of `S` before it's type-erased into `StoredMock`
+type DropMock<S> = Box<dyn FnMut(&mut S) -> MockResult<(), ()>>;
+
+type TraitMockKey = (&'static str, &'static str, TypeId);
+
+/// Mock of function returning `impl Trait` with ID and name of type returned by mock
+type StoredImplTraitMock = (TypeId, &'static str, StoredMock);
+
 thread_local!{
-    static MOCK_STORE: RefCell<HashMap<TypeId, Rc<RefCell<Box<FnMut<(), Output=()>>>>>> = RefCell::new(HashMap::new())
+    static MOCK_STORE: RefCell<HashMap<TypeId, StoredMock>> = RefCell::new(HashMap::new());
+    static TRAIT_MOCK_STORE: RefCell<HashMap<TraitMockKey, StoredMock>> = RefCell::new(HashMap::new());
+    static DROP_MOCK_STORE: RefCell<HashMap<TypeId, StoredMock>> = RefCell::new(HashMap::new());
+    static IMPL_TRAIT_MOCK_STORE: RefCell<HashMap<TypeId, StoredImplTraitMock>> = RefCell::new(HashMap::new());
+    static MOCK_CALL_COUNTS: RefCell<HashMap<TypeId, usize>> = RefCell::new(HashMap::new());
+    static TRAIT_MOCK_CALL_COUNTS: RefCell<HashMap<TraitMockKey, usize>> = RefCell::new(HashMap::new());
+    static EXPECTATIONS: RefCell<Vec<Expectation>> = const { RefCell::new(Vec::new()) };
+    static MOCK_USES: RefCell<HashMap<TypeId, MockUse>> = RefCell::new(HashMap::new());
+}
+
+/// Returns number of the current mock call starting from 1, calls are counted since the mock was set
+fn next_call_number<K: Hash + Eq>(call_counts: &'static LocalKey<RefCell<HashMap<K, usize>>>, key: K) -> usize {
+    call_counts.with(|counts_ref_cell| {
+        let mut counts = counts_ref_cell.borrow_mut();
+        let count = counts.entry(key).or_insert(0);
+        *count += 1;
+        *count
+    })
+}
+
+fn reset_call_count<K: Hash + Eq>(call_counts: &'static LocalKey<RefCell<HashMap<K, usize>>>, key: &K) {
+    let _ = call_counts.try_with(|counts_ref_cell| counts_ref_cell.borrow_mut().remove(key));
 }
 
 /// Clear all mocks in the ThreadLocal; only necessary if tests share threads
+///
+/// Pending expectations are removed too. Tests annotated with `#[mocktopus::mocktopus_test]` do it automatically.
 pub fn clear_mocks() {
     MOCK_STORE.with(|mock_ref_cell| {
         mock_ref_cell.borrow_mut().clear();
     });
+    TRAIT_MOCK_STORE.with(|mock_ref_cell| {
+        mock_ref_cell.borrow_mut().clear();
+    });
+    DROP_MOCK_STORE.with(|mock_ref_cell| {
+        mock_ref_cell.borrow_mut().clear();
+    });
+    IMPL_TRAIT_MOCK_STORE.with(|mock_ref_cell| {
+        mock_ref_cell.borrow_mut().clear();
+    });
+    MOCK_CALL_COUNTS.with(|counts_ref_cell| {
+        counts_ref_cell.borrow_mut().clear();
+    });
+    TRAIT_MOCK_CALL_COUNTS.with(|counts_ref_cell| {
+        counts_ref_cell.borrow_mut().clear();
+    });
+    EXPECTATIONS.with(|expectations_ref_cell| {
+        expectations_ref_cell.borrow_mut().clear();
+    });
+    MOCK_USES.with(|uses_ref_cell| {
+        uses_ref_cell.borrow_mut().clear();
+    });
+}
+
+/// Checks and removes all pending expectations set with [mock_expect](trait.Mockable.html#tymethod.mock_expect)
+///
+/// # Panics
+/// When any expectation was not met, the message lists all of them.
+pub fn verify_expectations() {
+    let expectations = EXPECTATIONS.with(|expectations_ref_cell| expectations_ref_cell.replace(Vec::new()));
+    let failures: Vec<_> = expectations.iter()
+        .filter(|expectation| expectation.calls.get() != expectation.expected)
+        .map(|expectation| format!("expected {} to be called {} times, but it was called {} times",
+                                   expectation.fn_name, expectation.expected, expectation.calls.get()))
+        .collect();
+    if !failures.is_empty() {
+        panic!("{}", failures.join("\n"))
+    }
+}
+
+/// Checks that all mocks set with [Mockable](trait.Mockable.html) were called at least once
+///
+/// Mocks of functions, which aren't [mockable](https://docs.rs/mocktopus_macros), are rejected when they are set up,
+/// but generic functions and trait methods aren't checked. This check finds their mocks, which are never called,
+/// as well as mocks of mockable functions, which the test didn't call.
+/// It's done automatically after tests annotated with
+/// [#\[mocktopus::mocktopus_test(strict)\]](https://docs.rs/mocktopus_macros/*/mocktopus_macros/attr.mocktopus_test.html).
+/// Mocks are tracked since they were last [cleared](fn.clear_mocks.html), including mocks already removed
+/// by dropping their [MockGuard](struct.MockGuard.html). A function counts as called if any of its mocks was called.
+/// Mocks set by fakes and helpers of this crate, e.g. [InMemoryFs](../fs/struct.InMemoryFs.html), aren't tracked,
+/// because they mock wrappers, which are always mockable, and tests usually call only some of them.
+///
+/// # Panics
+/// When any mock wasn't called, the message lists names of the mocked functions.
+pub fn verify_mocks_called() {
+    let mut failures: Vec<_> = MOCK_USES.with(|uses_ref_cell| {
+        uses_ref_cell.borrow()
+            .values()
+            .filter(|mock_use| !mock_use.is_called)
+            .map(|mock_use| format!("mock of {} was never called", mock_use.fn_name))
+            .collect()
+    });
+    if !failures.is_empty() {
+        failures.sort();
+        panic!("{}", failures.join("\n"))
+    }
+}
+
+/// Runs test with clean mocks, used by
+/// [#\[mocktopus::mocktopus_test\]](https://docs.rs/mocktopus_macros/*/mocktopus_macros/attr.mocktopus_test.html)
+///
+/// Clears all mocks, runs the test and verifies [expectations](fn.verify_expectations.html).
+/// The mocks are cleared afterwards even if the test or verification panics.
+/// It can be used directly in custom harnesses, which run many tests on a single thread.
+pub fn run_test<R, F: FnOnce() -> R>(test: F) -> R {
+    clear_mocks();
+    let _clear_guard = ClearMocksGuard;
+    let result = test();
+    verify_expectations();
+    result
+}
+
+#[doc(hidden)]
+/// Sets mock receiving arguments as a single tuple, used by `mock!`
+pub fn mock_tuple<T: Tuple, O, F, M>(function: &F, mock: M)
+        where F: Mockable<T, O>, M: FnMut(T) -> MockResult<T, O> + 'static {
+    function.mock_safe(TupleMock(mock))
+}
+
+#[doc(hidden)]
+/// Sets scoped mock receiving arguments as a single tuple, used by `#[with_mocks]`
+pub fn mock_tuple_scoped<T: Tuple, O, F, M>(function: &F, mock: M) -> MockGuard
+        where F: Mockable<T, O>, M: FnMut(T) -> MockResult<T, O> + 'static {
+    function.mock_scoped(TupleMock(mock))
 }
 
-impl<T, O, F: FnOnce<T, Output=O>> Mockable<T, O> for F {
+#[doc(hidden)]
+/// Sets expected mock receiving arguments as a single tuple, used by `#[mockable]` companions
+pub fn mock_tuple_expect<T: Tuple, O, F, M>(function: &F, times: usize, mock: M)
+        where F: Mockable<T, O>, M: FnMut(T) -> MockResult<T, O> + 'static {
+    function.mock_expect(times, TupleMock(mock))
+}
+
+#[doc(hidden)]
+/// Sets mock returning clone of value, used by `#[mockable]` companions
+///
+/// The value type is generic, because return types with elided lifetimes can't be named outside of signature.
+/// Companions make the compiler check, that it's the function return type, which may differ only in lifetimes.
+pub fn mock_returns<T: Tuple, O: 'static, F, V>(function: &F, value: V)
+        where F: Mockable<T, O>, V: Clone + 'static {
+    mock_tuple(function, move |_| {
+        let mut returned = Some(value.clone());
+        let returned: &mut dyn Any = &mut returned;
+        match returned.downcast_mut::<Option<O>>().and_then(Option::take) {
+            Some(returned) => MockResult::Return(returned),
+            None => panic!("Mocktopus internal error: mock of {} returns value of type {} instead of {}",
+                           any::type_name::<F>(), any::type_name::<V>(), any::type_name::<O>()),
+        }
+    })
+}
+
+/// Mock closure receiving arguments as a single tuple
+struct TupleMock<M>(M);
+
+impl<T: Tuple, O, M: FnMut(T) -> MockResult<T, O>> FnOnce<T> for TupleMock<M> {
+    type Output = MockResult<T, O>;
+
+    extern "rust-call" fn call_once(mut self, args: T) -> MockResult<T, O> {
+        self.call_mut(args)
+    }
+}
+
+impl<T: Tuple, O, M: FnMut(T) -> MockResult<T, O>> FnMut<T> for TupleMock<M> {
+    extern "rust-call" fn call_mut(&mut self, args: T) -> MockResult<T, O> {
+        (self.0)(args)
+    }
+}
+
+struct ClearMocksGuard;
+
+impl Drop for ClearMocksGuard {
+    fn drop(&mut self) {
+        clear_mocks();
+    }
+}
+
+struct MockUse {
+    fn_name: &'static str,
+    is_called: bool,
+}
+
+struct Expectation {
+    fn_name: &'static str,
+    expected: usize,
+    calls: Rc<Cell<usize>>,
+}
+
+/// Mock closure counting its calls for an expectation
+struct CountingMock<M> {
+    mock: M,
+    calls: Rc<Cell<usize>>,
+}
+
+impl<T: Tuple, O, M: FnMut<T, Output=MockResult<T, O>>> FnOnce<T> for CountingMock<M> {
+    type Output = MockResult<T, O>;
+
+    extern "rust-call" fn call_once(mut self, args: T) -> MockResult<T, O> {
+        self.call_mut(args)
+    }
+}
+
+impl<T: Tuple, O, M: FnMut<T, Output=MockResult<T, O>>> FnMut<T> for CountingMock<M> {
+    extern "rust-call" fn call_mut(&mut self, args: T) -> MockResult<T, O> {
+        self.calls.set(self.calls.get() + 1);
+        self.mock.call_mut(args)
+    }
+}
+
+impl<T: Tuple, O, F: FnOnce<T, Output=O>> Mockable<T, O> for F {
     unsafe fn mock_raw<M: FnMut<T, Output=MockResult<T, O>>>(&self, mock: M) {
-        let id = self.get_mock_id();
-        MOCK_STORE.with(|mock_ref_cell| {
-            let real = Rc::new(RefCell::new(Box::new(mock) as Box<FnMut<_, Output=_>>));
-            let stored = transmute(real);
-            mock_ref_cell.borrow_mut()
-                .insert(id, stored);
-        })
+        insert_mock(self.get_mock_id(), any::type_name::<F>(), mock);
     }
 
     fn mock_safe<M: FnMut<T, Output=MockResult<T, O>> + 'static>(&self, mock: M) {
@@ -111,6 +415,27 @@ impl<T, O, F: FnOnce<T, Output=O>> Mockable<T, O> for F {
         }
     }
 
+    fn mock_scoped<M: FnMut<T, Output=MockResult<T, O>> + 'static>(&self, mock: M) -> MockGuard {
+        unsafe {
+            let id = self.get_mock_id();
+            MockGuard {
+                id,
+                store: &MOCK_STORE,
+                previous: insert_mock(id, any::type_name::<F>(), mock),
+            }
+        }
+    }
+
+    fn mock_expect<M: FnMut<T, Output=MockResult<T, O>> + 'static>(&self, times: usize, mock: M) {
+        let calls = Rc::new(Cell::new(0));
+        EXPECTATIONS.with(|expectations_ref_cell| expectations_ref_cell.borrow_mut().push(Expectation {
+            fn_name: any::type_name::<F>(),
+            expected: times,
+            calls: calls.clone(),
+        }));
+        self.mock_safe(CountingMock { mock, calls })
+    }
+
     fn call_mock(&self, input: T) -> MockResult<T, O> {
         unsafe {
             let id = self.get_mock_id();
@@ -123,8 +448,10 @@ impl<T, O, F: FnOnce<T, Output=O>> Mockable<T, O> for F {
                 .and_then(|rc| rc.try_borrow_mut().ok());
             match stored_opt {
                 Some(mut stored) => {
-                    let real: &mut Box<FnMut<_, Output=_>> = transmute(&mut*stored);
+                    mark_mock_called(id);
+                    let real: &mut Box<dyn FnMut<_, Output=MockResult<T, O>>> = transmute(&mut*stored);
                     real.call_mut(input)
+                        .with_call_info(any::type_name::<F>(), next_call_number(&MOCK_CALL_COUNTS, id))
                 }
                 None => MockResult::Continue(input),
             }
@@ -135,3 +462,590 @@ impl<T, O, F: FnOnce<T, Output=O>> Mockable<T, O> for F {
         (||()).type_id()
     }
 }
+
+unsafe fn insert_mock<T: Tuple, O, M: FnMut<T, Output=MockResult<T, O>>>(id: TypeId, fn_name: &'static str, mock: M)
+        -> Option<StoredMock> {
+    check_mockable(id, fn_name);
+    reset_call_count(&MOCK_CALL_COUNTS, &id);
+    MOCK_USES.with(|uses_ref_cell| {
+        uses_ref_cell.borrow_mut()
+            .entry(id)
+            .or_insert(MockUse { fn_name, is_called: false });
+    });
+    MOCK_STORE.with(|mock_ref_cell| {
+        let real = Rc::new(RefCell::new(Box::new(mock) as Box<dyn FnMut<_, Output=_>>));
+        let stored: StoredMock = transmute(real);
+        mock_ref_cell.borrow_mut()
+            .insert(id, stored)
+    })
+}
+
+/// IDs of functions made mockable, they are registered by `#[mockable]` before `main` runs
+static MOCKABLE_FNS: Mutex<BTreeSet<TypeId>> = Mutex::new(BTreeSet::new());
+
+/// Whether functions are registered as mockable on the target platform, see `__register_mockable!`
+const IS_REGISTRATION_SUPPORTED: bool = cfg!(any(target_os = "linux", target_os = "android", target_os = "freebsd",
+    target_os = "netbsd", target_os = "openbsd", target_os = "dragonfly", target_os = "illumos",
+    target_vendor = "apple", windows));
+
+/// Registers function made mockable, so it can be mocked.
+/// Functions without generic parameters call it with `__register_mockable!`.
+#[doc(hidden)]
+pub fn register_mockable<T: Tuple, O, F: Mockable<T, O>>(function: &F) {
+    let id = unsafe { function.get_mock_id() };
+    MOCKABLE_FNS.lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(id);
+}
+
+/// Keeps function registration linked in every binary, which calls the registered function
+#[doc(hidden)]
+pub fn retain_registration(registration: &extern "C" fn()) {
+    std::hint::black_box(registration);
+}
+
+/// Registers function made mockable before `main` runs, it's called by headers of mockable functions
+///
+/// The registration is placed in the platform's section of initializers. Platforms listed here must match
+/// `IS_REGISTRATION_SUPPORTED`, on other platforms mocks of functions aren't checked.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __register_mockable {
+    ($function:expr) => {{
+        #[used]
+        #[cfg_attr(any(target_os = "linux", target_os = "android", target_os = "freebsd", target_os = "netbsd",
+                       target_os = "openbsd", target_os = "dragonfly", target_os = "illumos"),
+                   link_section = ".init_array")]
+        #[cfg_attr(target_vendor = "apple", link_section = "__DATA,__mod_init_func")]
+        #[cfg_attr(windows, link_section = ".CRT$XCU")]
+        static REGISTRATION: extern "C" fn() = {
+            extern "C" fn register() {
+                $crate::mocking::register_mockable(&$function)
+            }
+            register
+        };
+        $crate::mocking::retain_registration(&REGISTRATION);
+    }};
+}
+
+/// Panics if function was not registered as mockable, so its mock would never be called
+fn check_mockable(id: TypeId, fn_name: &str) {
+    if !IS_REGISTRATION_SUPPORTED || !is_registrable(fn_name) {
+        return
+    }
+    let is_registered = MOCKABLE_FNS.lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .contains(&id);
+    if !is_registered {
+        panic!("{} isn't mockable, so its mock would never be called. Annotate it with #[mockable]", fn_name)
+    }
+}
+
+/// Generic functions and trait methods are never registered. Their names contain generic arguments,
+/// e.g. `module::generic<u32>`, or a trait, e.g. `<Struct as Trait>::method`, but not an impl, e.g. `<impl Struct>`.
+fn is_registrable(fn_name: &str) -> bool {
+    !fn_name.contains(" as ") && fn_name.match_indices('<')
+        .all(|(index, _)| index == 0 || fn_name[..index].ends_with("::"))
+}
+
+fn mark_mock_called(id: TypeId) {
+    MOCK_USES.with(|uses_ref_cell| {
+        if let Some(mock_use) = uses_ref_cell.borrow_mut().get_mut(&id) {
+            mock_use.is_called = true;
+        }
+    })
+}
+
+/// Guard of mock set with [mock_scoped](trait.Mockable.html#tymethod.mock_scoped)
+///
+/// When dropped, it restores mock, which was set before the guarded one or removes the mock if there was none.
+#[must_use = "mock is removed when guard is dropped"]
+pub struct MockGuard {
+    id: TypeId,
+    store: &'static LocalKey<RefCell<HashMap<TypeId, StoredMock>>>,
+    previous: Option<StoredMock>,
+}
+
+impl MockGuard {
+    /// Excludes the mock from [verify_mocks_called](fn.verify_mocks_called.html), used by fakes and helpers
+    pub(crate) fn unverified(self) -> Self {
+        mark_mock_called(self.id);
+        self
+    }
+}
+
+impl Drop for MockGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        let _replaced = self.store.try_with(|mock_ref_cell| {
+            let mut mock_store = mock_ref_cell.borrow_mut();
+            match previous {
+                Some(previous) => mock_store.insert(self.id, previous),
+                None => mock_store.remove(&self.id),
+            }
+        });
+        reset_call_count(&MOCK_CALL_COUNTS, &self.id);
+    }
+}
+
+/// Mocks of a fake, which replaces a group of mockable functions in the current thread while it's alive
+///
+/// Fakes set up mocks using their own state, so they don't make tests fail in
+/// [verify_mocks_called](fn.verify_mocks_called.html) if some of them aren't called.
+/// Dropping the mocks restores mocks, which were set before them, or removes them if there were none.
+pub(crate) struct FakeMocks {
+    _guards: Vec<MockGuard>,
+}
+
+impl FakeMocks {
+    pub(crate) fn new(guards: Vec<MockGuard>) -> Self {
+        FakeMocks {
+            _guards: guards.into_iter().map(MockGuard::unverified).collect(),
+        }
+    }
+}
+
+/// Trait for setting up mocks returning references borrowed from function argument
+///
+/// The trait is implemented for all functions taking a single reference and returning a reference with the same
+/// lifetime, e.g. `fn(&A) -> &R` or `fn(&self) -> &R`.
+/// Unlike [mock_safe](trait.Mockable.html#tymethod.mock_safe) the mock closure must work for any lifetime of the
+/// argument, so it can return data borrowed from it instead of leaking memory with `Box::leak`.
+///
+/// Note: methods have any effect only if called on functions [annotated as mockable](https://docs.rs/mocktopus_macros).
+pub trait MockableRef<A: ?Sized, R: ?Sized> {
+    /// Mocks function with closure returning reference borrowed from the argument
+    ///
+    /// The mock replaces one set with [Mockable](trait.Mockable.html) methods and vice versa.
+    ///
+    /// ```
+    /// #[mockable]
+    /// fn trim_first(text: &str) -> &str {
+    ///     text
+    /// }
+    ///
+    /// #[test]
+    /// fn trim_first_test() {
+    ///     trim_first.mock_ref(|text: &str| MockResult::Return(&text[1..]));
+    ///
+    ///     assert_eq!("bc", trim_first("abc"));
+    /// }
+    /// ```
+    fn mock_ref<M>(&self, mock: M) where M: for<'a> FnMut(&'a A) -> MockResult<(&'a A,), &'a R> + 'static;
+}
+
+impl<A: ?Sized + 'static, R: ?Sized + 'static, F> MockableRef<A, R> for F
+        where F: for<'a> FnOnce<(&'a A,), Output=&'a R> {
+    fn mock_ref<M>(&self, mock: M) where M: for<'a> FnMut(&'a A) -> MockResult<(&'a A,), &'a R> + 'static {
+        // The mock is valid for any lifetime, so it's safe to call it with one shorter than 'static
+        unsafe {
+            Mockable::<(&'static A,), &'static R>::mock_raw(self, mock)
+        }
+    }
+}
+
+/// Trait for setting up mocks returning mutable references borrowed from function argument
+///
+/// The trait is implemented for all functions taking a single mutable reference and returning a mutable reference
+/// with the same lifetime, e.g. `fn(&mut A) -> &mut R` or `fn(&mut self) -> &mut R`.
+/// It's a safe replacement of returning values converted with
+/// [as_mut](../mocking_utils/fn.as_mut.html), the mock receives the argument reborrowed and the returned
+/// reference can't outlive it.
+///
+/// Note: methods have any effect only if called on functions [annotated as mockable](https://docs.rs/mocktopus_macros).
+pub trait MockableMut<A: ?Sized, R: ?Sized> {
+    /// Mocks function with closure returning mutable reference borrowed from the argument
+    ///
+    /// The mock replaces one set with [Mockable](trait.Mockable.html) methods and vice versa.
+    ///
+    /// ```
+    /// #[mockable]
+    /// fn get_string(context: &mut Context) -> &mut String {
+    ///     context.get_mut_string()
+    /// }
+    ///
+    /// #[test]
+    /// fn get_string_test() {
+    ///     get_string.mock_mut(|context: &mut Context| MockResult::Return(&mut context.other_string));
+    ///
+    ///     let mut context = Context::default();
+    ///     get_string(&mut context).push_str("mocked");
+    ///     assert_eq!("mocked", context.other_string);
+    /// }
+    /// ```
+    fn mock_mut<M>(&self, mock: M)
+        where M: for<'a> FnMut(&'a mut A) -> MockResult<(&'a mut A,), &'a mut R> + 'static;
+}
+
+impl<A: ?Sized + 'static, R: ?Sized + 'static, F> MockableMut<A, R> for F
+        where F: for<'a> FnOnce<(&'a mut A,), Output=&'a mut R> {
+    fn mock_mut<M>(&self, mock: M)
+            where M: for<'a> FnMut(&'a mut A) -> MockResult<(&'a mut A,), &'a mut R> + 'static {
+        // The mock is valid for any lifetime, so it's safe to call it with one shorter than 'static
+        unsafe {
+            Mockable::<(&'static mut A,), &'static mut R>::mock_raw(self, mock)
+        }
+    }
+}
+
+/// Trait for setting up mocks of functions returning `impl Trait`
+///
+/// The trait is implemented for all functions, so its methods can be called on any function.
+/// Mocks of functions returning `impl Trait` can't be set up with [Mockable](trait.Mockable.html) to return a value,
+/// because type hidden behind `impl Trait` can't be named.
+///
+/// Note: methods have any effect only if called on functions [annotated as mockable](https://docs.rs/mocktopus_macros)
+/// and only if they return `impl Trait` directly.
+pub trait MockableImplTrait<T: Tuple, O> {
+    /// Sets up mock returning concrete type hidden behind `impl Trait` returned by mocked function
+    ///
+    /// The concrete type `C` must be exactly the type returned by the mocked function, which is checked at runtime.
+    /// If the types differ, the mocked function panics when called. If the function returns a boxed trait object
+    /// as `impl Trait`, the mock can return any value boxed the same way.
+    ///
+    /// Mocks set up with [Mockable](trait.Mockable.html) take precedence,
+    /// this mock is called only if they continue.
+    ///
+    /// ```
+    /// #[mockable]
+    /// fn get_numbers(count: u32) -> impl Iterator<Item = u32> {
+    ///     (0..count).collect::<Vec<_>>().into_iter()
+    /// }
+    ///
+    /// #[test]
+    /// fn get_numbers_test() {
+    ///     get_numbers.mock_impl_trait(|_| MockResult::Return(vec![5, 6].into_iter()));
+    ///
+    ///     assert_eq!(vec![5, 6], get_numbers(10).collect::<Vec<_>>());
+    /// }
+    /// ```
+    fn mock_impl_trait<C, M: FnMut<T, Output=MockResult<T, C>> + 'static>(&self, mock: M);
+
+    #[doc(hidden)]
+    /// Called before every execution of a mockable function returning `impl Trait`.
+    /// Checks if mock is set and if it is, calls it.
+    fn call_impl_trait_mock(&self, input: T) -> MockResult<T, O>;
+}
+
+impl<T: Tuple, O, F: FnOnce<T, Output=O>> MockableImplTrait<T, O> for F {
+    fn mock_impl_trait<C, M: FnMut<T, Output=MockResult<T, C>> + 'static>(&self, mock: M) {
+        unsafe {
+            let id = self.get_mock_id();
+            check_mockable(id, any::type_name::<F>());
+            IMPL_TRAIT_MOCK_STORE.with(|mock_ref_cell| {
+                let real = Rc::new(RefCell::new(Box::new(mock) as Box<dyn FnMut<_, Output=_>>));
+                let stored: StoredMock = transmute(real);
+                mock_ref_cell.borrow_mut()
+                    .insert(id, (get_type_id::<C>(), any::type_name::<C>(), stored));
+            })
+        }
+    }
+
+    fn call_impl_trait_mock(&self, input: T) -> MockResult<T, O> {
+        let input = match self.call_mock(input) {
+            MockResult::Continue(input) => input,
+            mock_result => return mock_result,
+        };
+        unsafe {
+            let id = self.get_mock_id();
+            let stored_opt = IMPL_TRAIT_MOCK_STORE.with(|mock_ref_cell|
+                mock_ref_cell.borrow()
+                    .get(&id)
+                    .cloned()
+            );
+            let (mock_type_id, mock_type_name, rc) = match stored_opt {
+                Some(stored) => stored,
+                None => return MockResult::Continue(input),
+            };
+            if mock_type_id != get_type_id::<O>() {
+                panic!("mock of function {} returns {}, but the function returns {}",
+                       any::type_name::<F>(), mock_type_name, any::type_name::<O>())
+            }
+            let mock_result = match rc.try_borrow_mut() {
+                Ok(mut stored) => {
+                    let real: &mut Box<dyn FnMut<_, Output=MockResult<T, O>>> = transmute(&mut*stored);
+                    real.call_mut(input)
+                        .with_call_info(any::type_name::<F>(), next_call_number(&MOCK_CALL_COUNTS, id))
+                }
+                Err(_) => MockResult::Continue(input),
+            };
+            mock_result
+        }
+    }
+}
+
+/// Implementor of trait, on which trait method [mocked for all implementors](fn.mock_trait_method.html) was called
+///
+/// It gives access to receiver of called method. Static trait methods do not have receiver,
+/// but still provide name of implementing type.
+pub struct TraitImplementor<'a> {
+    type_name: &'static str,
+    type_id: TypeId,
+    receiver: Option<*const ()>,
+    _receiver_lifetime: PhantomData<&'a ()>,
+}
+
+impl<'a> TraitImplementor<'a> {
+    #[doc(hidden)]
+    /// Called by mockable trait methods with reference to `self` if the method has it.
+    pub fn new<S: ?Sized + 'static>(receiver: Option<&'a S>) -> Self {
+        TraitImplementor {
+            type_name: any::type_name::<S>(),
+            type_id: get_type_id::<S>(),
+            receiver: receiver.map(|receiver| receiver as *const S as *const ()),
+            _receiver_lifetime: PhantomData,
+        }
+    }
+
+    #[doc(hidden)]
+    /// Called by mockable trait methods of implementors, which may be not `'static`.
+    /// Type IDs ignore lifetimes, so receiver of such implementor could be downcast to a type outliving it.
+    pub fn without_receiver<S: ?Sized>() -> Self {
+        TraitImplementor {
+            type_name: any::type_name::<S>(),
+            type_id: get_type_id::<S>(),
+            receiver: None,
+            _receiver_lifetime: PhantomData,
+        }
+    }
+
+    /// Name of implementing type
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Checks if implementing type is `T`
+    pub fn is<T: ?Sized + 'static>(&self) -> bool {
+        self.type_id == get_type_id::<T>()
+    }
+
+    /// Returns receiver of called method if the method has it and implementing type is `T`
+    ///
+    /// Receiver is available only in annotated trait impls without generic type or lifetime parameters
+    /// for types without lifetimes other than `'static`, because only they are known to be `'static`.
+    /// It's never available in trait default methods.
+    pub fn downcast_ref<T: 'static>(&self) -> Option<&T> {
+        match self.receiver {
+            Some(receiver) if self.is::<T>() => Some(unsafe { &*(receiver as *const T) }),
+            _ => None,
+        }
+    }
+}
+
+/// Mocks trait method for all its mockable implementors
+///
+/// Only implementors from [annotated trait impls](https://docs.rs/mocktopus_macros) and implementors using
+/// annotated trait default methods are affected. Implementor-specific mocks set up with [Mockable](trait.Mockable.html)
+/// take precedence, the trait method mock is called only if they continue.
+///
+/// The mock closure receives [TraitImplementor](struct.TraitImplementor.html) followed by method arguments
+/// without receiver. It's the simplest to use it through
+/// [mock_trait_method!](../macro.mock_trait_method.html) macro.
+///
+/// Trait method is identified by trait and method names, so same-named traits are mocked together.
+/// Trait methods with signatures differing from the one of the mock are not mocked.
+/// Trait methods with more than 12 arguments without receiver can be mocked only for specific implementors.
+pub fn mock_trait_method<I, O, M>(trait_name: &'static str, method_name: &'static str, mock: M)
+        where I: TraitMockInput, M: FnMut<I, Output=MockResult<I::Args, O>> + 'static {
+    let key = (trait_name, method_name, get_type_id::<(I::Args, O)>());
+    reset_call_count(&TRAIT_MOCK_CALL_COUNTS, &key);
+    TRAIT_MOCK_STORE.with(|mock_ref_cell| unsafe {
+        let real = Rc::new(RefCell::new(Box::new(mock) as Box<dyn FnMut<_, Output=_>>));
+        let stored: StoredMock = transmute(real);
+        mock_ref_cell.borrow_mut()
+            .insert(key, stored);
+    })
+}
+
+#[doc(hidden)]
+/// Called by mockable trait methods if implementor-specific mock continued
+pub fn call_trait_mock<'a, T: TraitMethodArgs<'a>, O>(trait_name: &'static str, method_name: &'static str,
+                                                      implementor: TraitImplementor<'a>, input: T) -> MockResult<T, O> {
+    unsafe {
+        let key = (trait_name, method_name, get_type_id::<(T, O)>());
+        let rc_opt = TRAIT_MOCK_STORE.with(|mock_ref_cell|
+            mock_ref_cell.borrow()
+                .get(&key)
+                .cloned()
+        );
+        let stored_opt = rc_opt.as_ref()
+            .and_then(|rc| rc.try_borrow_mut().ok());
+        match stored_opt {
+            Some(mut stored) => {
+                let real: &mut Box<dyn FnMut<T::WithImplementor, Output=MockResult<T, O>>> = transmute(&mut*stored);
+                real.call_mut(input.with_implementor(implementor))
+                    .with_call_info(format_args!("{}::{}", trait_name, method_name),
+                                    next_call_number(&TRAIT_MOCK_CALL_COUNTS, key))
+            }
+            None => MockResult::Continue(input),
+        }
+    }
+}
+
+#[doc(hidden)]
+/// Tuple of trait method arguments without receiver
+pub trait TraitMethodArgs<'a> {
+    type WithImplementor: Tuple;
+
+    fn with_implementor(self, implementor: TraitImplementor<'a>) -> Self::WithImplementor;
+}
+
+#[doc(hidden)]
+/// Tuple of trait method mock closure arguments
+pub trait TraitMockInput: Tuple {
+    type Args;
+}
+
+macro_rules! impl_trait_method_args {
+    ($($arg:ident),*) => {
+        impl<'a, $($arg),*> TraitMethodArgs<'a> for ($($arg,)*) {
+            type WithImplementor = (TraitImplementor<'a>, $($arg,)*);
+
+            #[allow(non_snake_case)]
+            fn with_implementor(self, implementor: TraitImplementor<'a>) -> Self::WithImplementor {
+                let ($($arg,)*) = self;
+                (implementor, $($arg,)*)
+            }
+        }
+
+        impl<'a, $($arg),*> TraitMockInput for (TraitImplementor<'a>, $($arg,)*) {
+            type Args = ($($arg,)*);
+        }
+    }
+}
+
+impl_trait_method_args!();
+impl_trait_method_args!(A1);
+impl_trait_method_args!(A1, A2);
+impl_trait_method_args!(A1, A2, A3);
+impl_trait_method_args!(A1, A2, A3, A4);
+impl_trait_method_args!(A1, A2, A3, A4, A5);
+impl_trait_method_args!(A1, A2, A3, A4, A5, A6);
+impl_trait_method_args!(A1, A2, A3, A4, A5, A6, A7);
+impl_trait_method_args!(A1, A2, A3, A4, A5, A6, A7, A8);
+impl_trait_method_args!(A1, A2, A3, A4, A5, A6, A7, A8, A9);
+impl_trait_method_args!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10);
+impl_trait_method_args!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11);
+impl_trait_method_args!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12);
+
+/// Mocks trait method for all its mockable implementors
+///
+/// See [mock_trait_method](mocking/fn.mock_trait_method.html) for details.
+///
+/// ```
+/// #[mockable]
+/// impl Plugin for FirstPlugin {
+///     fn name(&self, prefix: &str) -> String { ... }
+/// }
+///
+/// #[mockable]
+/// impl Plugin for SecondPlugin {
+///     fn name(&self, prefix: &str) -> String { ... }
+/// }
+///
+/// #[test]
+/// fn plugin_test() {
+///     mock_trait_method!(Plugin::name, |this: TraitImplementor, prefix: &str|
+///         MockResult::Return(format!("{} {}", prefix, this.type_name())));
+///
+///     assert_eq!("mocked my_crate::FirstPlugin", FirstPlugin.name("mocked"));
+///     assert_eq!("mocked my_crate::SecondPlugin", SecondPlugin.name("mocked"));
+/// }
+/// ```
+#[macro_export]
+macro_rules! mock_trait_method {
+    ($trait_name:ident :: $method_name:ident, $mock:expr) => {
+        $crate::mocking::mock_trait_method(stringify!($trait_name), stringify!($method_name), $mock)
+    };
+    ($path_segment:ident :: $($rest:tt)+) => {
+        $crate::mock_trait_method!($($rest)+)
+    };
+}
+
+/// Mocks dropping of values of type `S`
+///
+/// Only types with [annotated](https://docs.rs/mocktopus_macros) `Drop` impls are affected. The impls must not have
+/// generic type or lifetime parameters and the type must be `'static`, other `Drop` impls are never made mockable.
+/// The mock closure is called with dropped value before body of `Drop::drop` runs.
+/// If it returns `MockResult::Continue`, the body runs normally.
+/// If it returns `MockResult::Return`, the body is skipped, but fields of the value are still dropped,
+/// so no resources owned by them leak.
+///
+/// Value is dropped only once, so unlike with [Mockable](trait.Mockable.html) the mock can't replace it.
+/// Failing drop can be simulated by panicking inside the mock closure.
+///
+/// ```
+/// #[mockable]
+/// impl Drop for Connection {
+///     fn drop(&mut self) {
+///         self.close();
+///     }
+/// }
+///
+/// #[test]
+/// fn connection_test() {
+///     mock_drop(|connection: &mut Connection| {
+///         assert!(connection.is_flushed());
+///         MockResult::Return(())
+///     });
+///
+///     drop(Connection::open());
+/// }
+/// ```
+pub fn mock_drop<S: 'static, M>(mock: M) where M: FnMut(&mut S) -> MockResult<(), ()> + 'static {
+    insert_drop_mock(mock);
+}
+
+/// Sets up drop mock like [mock_drop](fn.mock_drop.html), which is removed when the returned guard is dropped
+pub(crate) fn mock_drop_scoped<S: 'static, M>(mock: M) -> MockGuard
+        where M: FnMut(&mut S) -> MockResult<(), ()> + 'static {
+    MockGuard {
+        id: get_type_id::<S>(),
+        store: &DROP_MOCK_STORE,
+        previous: insert_drop_mock(mock),
+    }
+}
+
+fn insert_drop_mock<S: 'static, M>(mock: M) -> Option<StoredMock>
+        where M: FnMut(&mut S) -> MockResult<(), ()> + 'static {
+    let id = get_type_id::<S>();
+    reset_call_count(&MOCK_CALL_COUNTS, &id);
+    DROP_MOCK_STORE.with(|mock_ref_cell| unsafe {
+        let real = Rc::new(RefCell::new(Box::new(mock) as DropMock<S>));
+        let stored: StoredMock = transmute(real);
+        mock_ref_cell.borrow_mut()
+            .insert(id, stored)
+    })
+}
+
+#[doc(hidden)]
+/// Called before every execution of a mockable `Drop::drop`. Checks if drop mock is set and if it is, calls it.
+///
+/// Only `Drop` impls of `'static` types are mockable, because mocks are stored by type ID, which ignores lifetimes.
+pub fn call_drop_mock<S: 'static>(dropped: &mut S) -> MockResult<(), ()> {
+    unsafe {
+        let id = get_type_id::<S>();
+        let rc_opt = DROP_MOCK_STORE.with(|mock_ref_cell|
+            mock_ref_cell.borrow()
+                .get(&id)
+                .cloned()
+        );
+        let stored_opt = rc_opt.as_ref()
+            .and_then(|rc| rc.try_borrow_mut().ok());
+        match stored_opt {
+            Some(mut stored) => {
+                let real: &mut DropMock<S> = transmute(&mut*stored);
+                real(dropped)
+                    .with_call_info(format_args!("drop of {}", any::type_name::<S>()),
+                                    next_call_number(&MOCK_CALL_COUNTS, id))
+            }
+            None => MockResult::Continue(()),
+        }
+    }
+}
+
+/// Returns ID unique for `T`, because the closure type depends on generic parameters of the function
+#[allow(clippy::extra_unused_type_parameters)]
+fn get_type_id<T: ?Sized>() -> TypeId {
+    (||()).type_id()
+}
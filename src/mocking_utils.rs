@@ -1,9 +1,17 @@
+use crate::mocking::{mock_drop_scoped, MockGuard, MockResult};
+use std::cell::Cell;
+use std::marker::PhantomData;
+use std::rc::Rc;
+
 /// Converts non-mutable reference to a mutable one
 ///
 /// Allows creating multiple mutable references to a single item breaking Rust's safety policy.
 /// # Safety
 /// Use with extreme caution, may cause all sorts of mutability related undefined behaviors!
 ///
+/// Mocks of functions returning mutable references borrowed from a mutable reference argument should be set up with
+/// [mock_mut](../mocking/trait.MockableMut.html#tymethod.mock_mut) instead, which is safe.
+///
 /// One safe use case is when mocking function, which gets called only once during whole test execution, for example:
 ///
 /// ```
@@ -23,6 +31,62 @@
 ///     assert_eq!("mocked", get_string(&mut Context::default()));
 /// }
 /// ```
+#[allow(clippy::mut_from_ref, invalid_reference_casting)]
 pub unsafe fn as_mut<T>(t_ref: &T) -> &mut T {
     &mut *(t_ref as *const T as *mut T)
 }
+
+/// Counts drops of values of type `S`
+///
+/// Creating observer [mocks drop](../mocking/fn.mock_drop.html) of `S` with a closure, which counts calls and lets
+/// `Drop::drop` run normally. It replaces any previously set drop mock of `S` until the observer is dropped.
+/// Only types with [annotated](https://docs.rs/mocktopus_macros) `Drop` impls can be observed.
+///
+/// ```
+/// #[mockable]
+/// impl Drop for Guard {
+///     fn drop(&mut self) { ... }
+/// }
+///
+/// #[test]
+/// fn guard_test() {
+///     let observer = DropObserver::<Guard>::new();
+///
+///     with_guard(|_guard| ());
+///
+///     assert_eq!(1, observer.drop_count());
+/// }
+/// ```
+pub struct DropObserver<S> {
+    drop_count: Rc<Cell<usize>>,
+    _guard: MockGuard,
+    _dropped: PhantomData<fn(S)>,
+}
+
+impl<S: 'static> DropObserver<S> {
+    /// Creates observer and starts counting drops
+    pub fn new() -> Self {
+        let drop_count = Rc::new(Cell::new(0));
+        let mock_drop_count = drop_count.clone();
+        let guard = mock_drop_scoped(move |_: &mut S| {
+            mock_drop_count.set(mock_drop_count.get() + 1);
+            MockResult::Continue(())
+        });
+        DropObserver {
+            drop_count,
+            _guard: guard,
+            _dropped: PhantomData,
+        }
+    }
+
+    /// Number of values dropped since observer creation
+    pub fn drop_count(&self) -> usize {
+        self.drop_count.get()
+    }
+}
+
+impl<S: 'static> Default for DropObserver<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,351 @@
+use crate::mocking::{FakeMocks, Mockable, MockResult};
+use mocktopus_macros::mockable;
+use std::cell::RefCell;
+use std::cmp;
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Read, Write};
+use std::net::{self, SocketAddr};
+use std::rc::Rc;
+
+/// Mockable facade of [net::TcpStream](https://doc.rust-lang.org/std/net/struct.TcpStream.html)
+///
+/// The stream is either a real one or a fake one connected to a [LoopbackNet](struct.LoopbackNet.html) peer.
+pub struct TcpStream(TcpStreamInner);
+
+enum TcpStreamInner {
+    Real(net::TcpStream),
+    Fake(FakeConnection),
+}
+
+#[mockable(mocktopus = "crate")]
+impl TcpStream {
+    /// Opens connection to given address, which can be a host name with port,
+    /// see [net::TcpStream::connect](https://doc.rust-lang.org/std/net/struct.TcpStream.html#method.connect)
+    pub fn connect(addr: &str) -> io::Result<TcpStream> {
+        net::TcpStream::connect(addr).map(|stream| TcpStream(TcpStreamInner::Real(stream)))
+    }
+
+    /// Reads received bytes, returns 0 if connection was closed by peer
+    pub fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.0 {
+            TcpStreamInner::Real(ref mut stream) => stream.read(buf),
+            TcpStreamInner::Fake(ref mut connection) => connection.read(buf),
+        }
+    }
+
+    /// Sends bytes, returns number of bytes sent
+    pub fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.0 {
+            TcpStreamInner::Real(ref mut stream) => stream.write(buf),
+            TcpStreamInner::Fake(ref mut connection) => connection.write(buf),
+        }
+    }
+}
+
+impl Read for TcpStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        TcpStream::read(self, buf)
+    }
+}
+
+impl Write for TcpStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        TcpStream::write(self, buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.0 {
+            TcpStreamInner::Real(ref mut stream) => stream.flush(),
+            TcpStreamInner::Fake(_) => Ok(()),
+        }
+    }
+}
+
+/// Mockable facade of [net::UdpSocket](https://doc.rust-lang.org/std/net/struct.UdpSocket.html)
+///
+/// The socket is either a real one or a fake one exchanging datagrams with [LoopbackNet](struct.LoopbackNet.html)
+/// peers.
+pub struct UdpSocket(UdpSocketInner);
+
+enum UdpSocketInner {
+    Real(net::UdpSocket),
+    Fake(FakeUdpSocket),
+}
+
+#[mockable(mocktopus = "crate")]
+impl UdpSocket {
+    /// Creates socket bound to given address,
+    /// see [net::UdpSocket::bind](https://doc.rust-lang.org/std/net/struct.UdpSocket.html#method.bind)
+    pub fn bind(addr: SocketAddr) -> io::Result<UdpSocket> {
+        net::UdpSocket::bind(addr).map(|socket| UdpSocket(UdpSocketInner::Real(socket)))
+    }
+
+    /// Sends datagram to given address, returns number of bytes sent
+    pub fn send_to(&self, buf: &[u8], addr: SocketAddr) -> io::Result<usize> {
+        match self.0 {
+            UdpSocketInner::Real(ref socket) => socket.send_to(buf, addr),
+            UdpSocketInner::Fake(ref socket) => Ok(socket.send_to(buf, addr)),
+        }
+    }
+
+    /// Receives datagram, returns its size and sender address
+    pub fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        match self.0 {
+            UdpSocketInner::Real(ref socket) => socket.recv_from(buf),
+            UdpSocketInner::Fake(ref socket) => socket.recv_from(buf),
+        }
+    }
+}
+
+/// Network fake, which replaces real network in current thread with scripted peers
+///
+/// Creating fake mocks [TcpStream::connect](struct.TcpStream.html#method.connect) and
+/// [UdpSocket::bind](struct.UdpSocket.html#method.bind), so they create fake connections and sockets.
+/// Connecting to an address without a scripted peer is refused, datagrams sent to such addresses are lost.
+/// No real sockets are created and everything stays in the current thread, so operations, which would block,
+/// fail with `io::ErrorKind::WouldBlock` instead.
+/// After the fake is dropped, connecting and binding work as before its creation, but already created
+/// fake connections and sockets keep working.
+///
+/// ```
+/// fn fetch_greeting(addr: &str) -> io::Result<String> {
+///     let mut stream = TcpStream::connect(addr)?;
+///     stream.write_all(b"HELLO\n")?;
+///     let mut greeting = String::new();
+///     stream.read_to_string(&mut greeting)?;
+///     Ok(greeting)
+/// }
+///
+/// #[test]
+/// fn fetch_greeting_test() {
+///     let net = LoopbackNet::new();
+///     let server = net.tcp_peer("server:1234").send("hi there");
+///
+///     assert_eq!("hi there", fetch_greeting("server:1234").unwrap());
+///     assert_eq!(b"HELLO\n".to_vec(), server.received());
+/// }
+/// ```
+pub struct LoopbackNet {
+    state: Rc<RefCell<NetState>>,
+    _mocks: FakeMocks,
+}
+
+impl LoopbackNet {
+    /// Creates network without any peers and mocks `TcpStream` and `UdpSocket` with it
+    pub fn new() -> Self {
+        let state = Rc::new(RefCell::new(NetState::default()));
+        let mocks = FakeMocks::new(vec![
+            TcpStream::connect.mock_scoped({
+                let state = state.clone();
+                move |addr| MockResult::Return(state.borrow().connect(addr))
+            }),
+            UdpSocket::bind.mock_scoped({
+                let state = state.clone();
+                move |addr| MockResult::Return(Ok(UdpSocket(UdpSocketInner::Fake(FakeUdpSocket {
+                    local_addr: addr,
+                    incoming: RefCell::new(VecDeque::new()),
+                    net: state.clone(),
+                }))))
+            }),
+        ]);
+        LoopbackNet {
+            state,
+            _mocks: mocks,
+        }
+    }
+
+    /// Creates TCP peer accepting connections on given address, replaces existing one
+    pub fn tcp_peer(&self, addr: &str) -> TcpPeer {
+        let peer = Rc::new(RefCell::new(TcpPeerState::default()));
+        self.state.borrow_mut().tcp_peers.insert(addr.to_string(), peer.clone());
+        TcpPeer(peer)
+    }
+
+    /// Creates UDP peer receiving datagrams on given address, replaces existing one
+    pub fn udp_peer(&self, addr: SocketAddr) -> UdpPeer {
+        let peer = Rc::new(RefCell::new(UdpPeerState::default()));
+        self.state.borrow_mut().udp_peers.insert(addr, peer.clone());
+        UdpPeer(peer)
+    }
+}
+
+impl Default for LoopbackNet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Script of a TCP peer created with [LoopbackNet::tcp_peer](struct.LoopbackNet.html#method.tcp_peer)
+///
+/// Each connection to the peer gets all bytes scripted with `send` and then it's closed by the peer.
+#[derive(Clone)]
+pub struct TcpPeer(Rc<RefCell<TcpPeerState>>);
+
+impl TcpPeer {
+    /// Adds bytes sent by peer to each connection
+    pub fn send<B: Into<Vec<u8>>>(self, bytes: B) -> Self {
+        self.0.borrow_mut().sent.extend(bytes.into());
+        self
+    }
+
+    /// Makes peer refuse connections
+    pub fn refuse(self) -> Self {
+        self.0.borrow_mut().refuse = true;
+        self
+    }
+
+    /// Resets each connection after `n` bytes were read from it
+    pub fn reset_after_read(self, n: usize) -> Self {
+        self.0.borrow_mut().reset_after_read = Some(n);
+        self
+    }
+
+    /// Resets each connection after `n` bytes were written to it
+    pub fn reset_after_write(self, n: usize) -> Self {
+        self.0.borrow_mut().reset_after_write = Some(n);
+        self
+    }
+
+    /// Bytes received by peer from all connections
+    pub fn received(&self) -> Vec<u8> {
+        self.0.borrow().received.clone()
+    }
+
+    /// Number of accepted connections
+    pub fn connection_count(&self) -> usize {
+        self.0.borrow().connection_count
+    }
+}
+
+/// Script of a UDP peer created with [LoopbackNet::udp_peer](struct.LoopbackNet.html#method.udp_peer)
+#[derive(Clone)]
+pub struct UdpPeer(Rc<RefCell<UdpPeerState>>);
+
+impl UdpPeer {
+    /// Adds reply, each received datagram is answered with the next reply until they run out
+    pub fn reply<B: Into<Vec<u8>>>(self, datagram: B) -> Self {
+        self.0.borrow_mut().replies.push_back(datagram.into());
+        self
+    }
+
+    /// Datagrams received by peer
+    pub fn received(&self) -> Vec<Vec<u8>> {
+        self.0.borrow().received.clone()
+    }
+}
+
+#[derive(Default)]
+struct NetState {
+    tcp_peers: HashMap<String, Rc<RefCell<TcpPeerState>>>,
+    udp_peers: HashMap<SocketAddr, Rc<RefCell<UdpPeerState>>>,
+}
+
+impl NetState {
+    fn connect(&self, addr: &str) -> io::Result<TcpStream> {
+        let peer = match self.tcp_peers.get(addr) {
+            Some(peer) if !peer.borrow().refuse => peer.clone(),
+            _ => return Err(io::Error::new(io::ErrorKind::ConnectionRefused,
+                                           format!("connection refused: {}", addr))),
+        };
+        let incoming = {
+            let mut peer_state = peer.borrow_mut();
+            peer_state.connection_count += 1;
+            peer_state.sent.iter().cloned().collect()
+        };
+        Ok(TcpStream(TcpStreamInner::Fake(FakeConnection {
+            peer,
+            incoming,
+            read_count: 0,
+            write_count: 0,
+            is_reset: false,
+        })))
+    }
+}
+
+#[derive(Default)]
+struct TcpPeerState {
+    sent: Vec<u8>,
+    refuse: bool,
+    reset_after_read: Option<usize>,
+    reset_after_write: Option<usize>,
+    received: Vec<u8>,
+    connection_count: usize,
+}
+
+struct FakeConnection {
+    peer: Rc<RefCell<TcpPeerState>>,
+    incoming: VecDeque<u8>,
+    read_count: usize,
+    write_count: usize,
+    is_reset: bool,
+}
+
+impl FakeConnection {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let limit = self.peer.borrow().reset_after_read;
+        let len = self.transfer_len(buf.len(), self.incoming.len(), self.read_count, limit)?;
+        for (byte, incoming) in buf.iter_mut().zip(self.incoming.drain(..len)) {
+            *byte = incoming;
+        }
+        self.read_count += len;
+        Ok(len)
+    }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let limit = self.peer.borrow().reset_after_write;
+        let len = self.transfer_len(buf.len(), buf.len(), self.write_count, limit)?;
+        self.peer.borrow_mut().received.extend_from_slice(&buf[..len]);
+        self.write_count += len;
+        Ok(len)
+    }
+
+    /// Returns number of bytes, which can be transferred before connection is reset
+    fn transfer_len(&mut self, buf_len: usize, available: usize, count: usize, limit: Option<usize>)
+            -> io::Result<usize> {
+        if limit.is_some_and(|limit| count >= limit) {
+            self.is_reset = true;
+        }
+        if self.is_reset {
+            return Err(io::Error::new(io::ErrorKind::ConnectionReset, "connection reset by peer"))
+        }
+        let len = cmp::min(buf_len, available);
+        Ok(limit.map_or(len, |limit| cmp::min(len, limit - count)))
+    }
+}
+
+#[derive(Default)]
+struct UdpPeerState {
+    replies: VecDeque<Vec<u8>>,
+    received: Vec<Vec<u8>>,
+}
+
+struct FakeUdpSocket {
+    local_addr: SocketAddr,
+    incoming: RefCell<VecDeque<(Vec<u8>, SocketAddr)>>,
+    net: Rc<RefCell<NetState>>,
+}
+
+impl FakeUdpSocket {
+    fn send_to(&self, buf: &[u8], addr: SocketAddr) -> usize {
+        if let Some(peer) = self.net.borrow().udp_peers.get(&addr) {
+            let mut peer = peer.borrow_mut();
+            peer.received.push(buf.to_vec());
+            if let Some(reply) = peer.replies.pop_front() {
+                self.incoming.borrow_mut().push_back((reply, addr));
+            }
+        }
+        buf.len()
+    }
+
+    fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        match self.incoming.borrow_mut().pop_front() {
+            Some((datagram, addr)) => {
+                let len = cmp::min(buf.len(), datagram.len());
+                buf[..len].copy_from_slice(&datagram[..len]);
+                Ok((len, addr))
+            },
+            None => Err(io::Error::new(io::ErrorKind::WouldBlock,
+                                       format!("no datagrams received by {}", self.local_addr))),
+        }
+    }
+}
@@ -0,0 +1,396 @@
+use crate::mocking::{FakeMocks, Mockable, MockResult};
+use mocktopus_macros::mockable;
+use std::cell::RefCell;
+use std::ffi::{OsStr, OsString};
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::rc::Rc;
+
+/// Mockable wrapper of [process::exit](https://doc.rust-lang.org/std/process/fn.exit.html)
+///
+/// Its mocks can't return, because the function never does, but they can unwind or panic.
+/// The simplest way to test code calling it is [catch_exit](fn.catch_exit.html).
+#[mockable(mocktopus = "crate")]
+pub fn exit(code: i32) -> ! {
+    process::exit(code)
+}
+
+/// Payload of panic raised by [exit](fn.exit.html) inside [catch_exit](fn.catch_exit.html)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Exit(pub i32);
+
+/// Runs closure with [exit](fn.exit.html) mocked to unwind instead of terminating the process
+///
+/// Returns `Err` with the exit code if the closure called `exit` or `Ok` with its result otherwise.
+/// Other panics are propagated. After returning the mock of `exit`, which was set before, is restored.
+///
+/// ```
+/// fn run(args: &[&str]) {
+///     if args.is_empty() {
+///         process::exit(2);
+///     }
+///     ...
+/// }
+///
+/// #[test]
+/// fn run_test() {
+///     assert_eq!(Err(2), catch_exit(|| run(&[])));
+/// }
+/// ```
+pub fn catch_exit<F: FnOnce() -> R, R>(f: F) -> Result<R, i32> {
+    let guard = exit.mock_scoped(|code| MockResult::Unwind(Box::new(Exit(code)))).unverified();
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    drop(guard);
+    match result {
+        Ok(returned) => Ok(returned),
+        Err(payload) => match payload.downcast::<Exit>() {
+            Ok(exit) => Err(exit.0),
+            Err(payload) => panic::resume_unwind(payload),
+        },
+    }
+}
+
+/// Mockable facade of [process::Command](https://doc.rust-lang.org/std/process/struct.Command.html)
+///
+/// Only `output`, `status` and `spawn` are mockable, other methods configure the command.
+/// Standard input, output and error have defaults of `process::Command`, they can't be configured.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Command {
+    program: OsString,
+    args: Vec<OsString>,
+    envs: Vec<(OsString, OsString)>,
+    current_dir: Option<PathBuf>,
+}
+
+impl Command {
+    /// Creates command running given program
+    pub fn new<S: AsRef<OsStr>>(program: S) -> Self {
+        Command {
+            program: program.as_ref().to_os_string(),
+            args: vec![],
+            envs: vec![],
+            current_dir: None,
+        }
+    }
+
+    /// Adds argument
+    pub fn arg<S: AsRef<OsStr>>(&mut self, arg: S) -> &mut Self {
+        self.args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    /// Adds multiple arguments
+    pub fn args<I: IntoIterator<Item=S>, S: AsRef<OsStr>>(&mut self, args: I) -> &mut Self {
+        self.args.extend(args.into_iter().map(|arg| arg.as_ref().to_os_string()));
+        self
+    }
+
+    /// Sets environment variable
+    pub fn env<K: AsRef<OsStr>, V: AsRef<OsStr>>(&mut self, key: K, value: V) -> &mut Self {
+        self.envs.push((key.as_ref().to_os_string(), value.as_ref().to_os_string()));
+        self
+    }
+
+    /// Sets working directory
+    pub fn current_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut Self {
+        self.current_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Program run by command
+    pub fn get_program(&self) -> &OsStr {
+        &self.program
+    }
+
+    /// Arguments of command
+    pub fn get_args(&self) -> &[OsString] {
+        &self.args
+    }
+
+    /// Environment variables set for command
+    pub fn get_envs(&self) -> &[(OsString, OsString)] {
+        &self.envs
+    }
+
+    /// Working directory set for command
+    pub fn get_current_dir(&self) -> Option<&Path> {
+        self.current_dir.as_deref()
+    }
+
+    /// Program and arguments separated with spaces
+    pub fn command_line(&self) -> String {
+        let mut command_line = self.program.to_string_lossy().into_owned();
+        for arg in &self.args {
+            command_line.push(' ');
+            command_line.push_str(&arg.to_string_lossy());
+        }
+        command_line
+    }
+
+    fn to_std(&self) -> process::Command {
+        let mut command = process::Command::new(&self.program);
+        command.args(&self.args);
+        command.envs(self.envs.iter().map(|(key, value)| (key, value)));
+        if let Some(ref current_dir) = self.current_dir {
+            command.current_dir(current_dir);
+        }
+        command
+    }
+}
+
+#[mockable(mocktopus = "crate")]
+impl Command {
+    /// Runs command and collects its output,
+    /// see [process::Command::output](https://doc.rust-lang.org/std/process/struct.Command.html#method.output)
+    pub fn output(&mut self) -> io::Result<Output> {
+        self.to_std().output().map(Output::from)
+    }
+
+    /// Runs command and waits for it to finish,
+    /// see [process::Command::status](https://doc.rust-lang.org/std/process/struct.Command.html#method.status)
+    pub fn status(&mut self) -> io::Result<ExitStatus> {
+        self.to_std().status().map(ExitStatus::from)
+    }
+
+    /// Starts command in background,
+    /// see [process::Command::spawn](https://doc.rust-lang.org/std/process/struct.Command.html#method.spawn)
+    pub fn spawn(&mut self) -> io::Result<Child> {
+        self.to_std().spawn().map(|child| Child(ChildInner::Real(child)))
+    }
+}
+
+/// Exit status of finished command, which can be created by mocks
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExitStatus {
+    code: Option<i32>,
+}
+
+impl ExitStatus {
+    /// Creates status of command, which exited with given code
+    pub fn from_code(code: i32) -> Self {
+        ExitStatus { code: Some(code) }
+    }
+
+    /// Returns true if command exited with code 0
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+
+    /// Exit code, `None` if command was terminated by a signal
+    pub fn code(&self) -> Option<i32> {
+        self.code
+    }
+}
+
+impl From<process::ExitStatus> for ExitStatus {
+    fn from(status: process::ExitStatus) -> Self {
+        ExitStatus { code: status.code() }
+    }
+}
+
+/// Output of finished command, which can be created by mocks
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Output {
+    /// Exit status
+    pub status: ExitStatus,
+    /// Data written to standard output
+    pub stdout: Vec<u8>,
+    /// Data written to standard error
+    pub stderr: Vec<u8>,
+}
+
+impl Output {
+    /// Creates output of command, which exited with given code
+    pub fn new<O: Into<Vec<u8>>, E: Into<Vec<u8>>>(code: i32, stdout: O, stderr: E) -> Self {
+        Output {
+            status: ExitStatus::from_code(code),
+            stdout: stdout.into(),
+            stderr: stderr.into(),
+        }
+    }
+}
+
+impl From<process::Output> for Output {
+    fn from(output: process::Output) -> Self {
+        Output {
+            status: output.status.into(),
+            stdout: output.stdout,
+            stderr: output.stderr,
+        }
+    }
+}
+
+/// Command started in background, either a real one or with output provided by a mock
+#[derive(Debug)]
+pub struct Child(ChildInner);
+
+#[derive(Debug)]
+enum ChildInner {
+    Real(process::Child),
+    Finished(Output),
+}
+
+impl Child {
+    /// Creates child, which has already finished with given output
+    pub fn from_output(output: Output) -> Self {
+        Child(ChildInner::Finished(output))
+    }
+
+    /// Waits for command to finish
+    pub fn wait(&mut self) -> io::Result<ExitStatus> {
+        match self.0 {
+            ChildInner::Real(ref mut child) => child.wait().map(ExitStatus::from),
+            ChildInner::Finished(ref output) => Ok(output.status),
+        }
+    }
+
+    /// Waits for command to finish and collects its output
+    pub fn wait_with_output(self) -> io::Result<Output> {
+        match self.0 {
+            ChildInner::Real(child) => child.wait_with_output().map(Output::from),
+            ChildInner::Finished(output) => Ok(output),
+        }
+    }
+
+    /// Kills command, does nothing if command has already finished
+    pub fn kill(&mut self) -> io::Result<()> {
+        match self.0 {
+            ChildInner::Real(ref mut child) => child.kill(),
+            ChildInner::Finished(_) => Ok(()),
+        }
+    }
+}
+
+/// Commands fake, which replaces running commands in current thread with scripted outputs
+///
+/// Creating script mocks [Command](struct.Command.html) methods `output`, `status` and `spawn`.
+/// Commands are matched by program and arguments, a command without a matching script fails with
+/// `io::ErrorKind::NotFound` like a missing program. All run commands are recorded.
+/// The script is in effect until it's dropped, then `Command` behaves as it did before the script was created.
+///
+/// ```
+/// fn current_commit() -> io::Result<String> {
+///     let output = Command::new("git").args(["rev-parse", "HEAD"]).output()?;
+///     Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+/// }
+///
+/// #[test]
+/// fn current_commit_test() {
+///     let script = CommandScript::new();
+///     script.on("git rev-parse HEAD").stdout("abc123\n");
+///
+///     assert_eq!("abc123", current_commit().unwrap());
+///     assert_eq!(vec!["git rev-parse HEAD"], script.executed());
+/// }
+/// ```
+pub struct CommandScript {
+    state: Rc<RefCell<ScriptState>>,
+    _mocks: FakeMocks,
+}
+
+impl CommandScript {
+    /// Creates script without any commands and mocks `Command` with it
+    pub fn new() -> Self {
+        let state = Rc::new(RefCell::new(ScriptState::default()));
+        let mocks = FakeMocks::new(vec![
+            Command::output.mock_scoped({
+                let state = state.clone();
+                move |command| MockResult::Return(state.borrow_mut().run(command))
+            }),
+            Command::status.mock_scoped({
+                let state = state.clone();
+                move |command| MockResult::Return(state.borrow_mut().run(command).map(|output| output.status))
+            }),
+            Command::spawn.mock_scoped({
+                let state = state.clone();
+                move |command| MockResult::Return(state.borrow_mut().run(command).map(Child::from_output))
+            }),
+        ]);
+        CommandScript {
+            state,
+            _mocks: mocks,
+        }
+    }
+
+    /// Scripts command, which exits with code 0 and no output unless configured otherwise
+    ///
+    /// The command line is split on whitespace into program and arguments, which must be matched exactly.
+    /// If multiple scripts match a command, the last one is used.
+    pub fn on(&self, command_line: &str) -> ScriptedCommand {
+        let mut state = self.state.borrow_mut();
+        state.scripts.push(Script {
+            command_line: command_line.split_whitespace().map(str::to_string).collect(),
+            output: Output::new(0, "", ""),
+        });
+        ScriptedCommand {
+            state: self.state.clone(),
+            index: state.scripts.len() - 1,
+        }
+    }
+
+    /// Command lines of all run commands in order of execution
+    pub fn executed(&self) -> Vec<String> {
+        self.state.borrow().executed.clone()
+    }
+}
+
+impl Default for CommandScript {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Script of a single command created with [CommandScript::on](struct.CommandScript.html#method.on)
+pub struct ScriptedCommand {
+    state: Rc<RefCell<ScriptState>>,
+    index: usize,
+}
+
+impl ScriptedCommand {
+    /// Sets data written to standard output
+    pub fn stdout<S: Into<Vec<u8>>>(self, stdout: S) -> Self {
+        self.state.borrow_mut().scripts[self.index].output.stdout = stdout.into();
+        self
+    }
+
+    /// Sets data written to standard error
+    pub fn stderr<S: Into<Vec<u8>>>(self, stderr: S) -> Self {
+        self.state.borrow_mut().scripts[self.index].output.stderr = stderr.into();
+        self
+    }
+
+    /// Sets exit code
+    pub fn exit_code(self, code: i32) -> Self {
+        self.state.borrow_mut().scripts[self.index].output.status = ExitStatus::from_code(code);
+        self
+    }
+}
+
+#[derive(Default)]
+struct ScriptState {
+    scripts: Vec<Script>,
+    executed: Vec<String>,
+}
+
+struct Script {
+    command_line: Vec<String>,
+    output: Output,
+}
+
+impl ScriptState {
+    fn run(&mut self, command: &Command) -> io::Result<Output> {
+        self.executed.push(command.command_line());
+        let command_line: Vec<_> = Some(command.get_program()).into_iter()
+            .chain(command.get_args().iter().map(OsString::as_os_str))
+            .map(OsStr::to_string_lossy)
+            .collect();
+        self.scripts.iter()
+            .rev()
+            .find(|script| script.command_line == command_line)
+            .map(|script| script.output.clone())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound,
+                                          format!("command not scripted: {}", command.command_line())))
+    }
+}
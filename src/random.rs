@@ -0,0 +1,247 @@
+use crate::mocking::{Mockable, MockGuard, MockResult};
+use mocktopus_macros::mockable;
+use std::any;
+use std::collections::hash_map::RandomState;
+use std::env;
+use std::hash::{BuildHasher, Hasher};
+use std::marker::Tuple;
+use std::thread;
+
+/// Name of environment variable, which overrides seed returned by [test_seed](fn.test_seed.html)
+pub const SEED_ENV_VAR: &str = "MOCKTOPUS_SEED";
+
+/// Mockable source of random numbers, which doesn't need any external crate
+///
+/// The values come from the randomly seeded hasher used by `HashMap`, they are good enough for jitter or sampling,
+/// but they aren't cryptographically secure.
+#[mockable(mocktopus = "crate")]
+pub fn random_u64() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(0);
+    hasher.finish()
+}
+
+/// Mockable source of random numbers in range [0, 1), see [random_u64](fn.random_u64.html)
+#[mockable(mocktopus = "crate")]
+pub fn random_f64() -> f64 {
+    to_f64(random_u64())
+}
+
+/// Returns seed for a randomized test
+///
+/// The seed is read from environment variable `MOCKTOPUS_SEED` if it's set, otherwise it's random.
+/// This allows replaying a test run with seed reported by
+/// [mock_seeded](trait.MockableRandom.html#tymethod.mock_seeded).
+///
+/// # Panics
+/// When environment variable is set, but it isn't a valid `u64`.
+pub fn test_seed() -> u64 {
+    match env::var(SEED_ENV_VAR) {
+        Ok(seed) => seed.trim().parse()
+            .unwrap_or_else(|_| panic!("{} is not a valid seed: {:?}", SEED_ENV_VAR, seed)),
+        Err(_) => RandomState::new().build_hasher().finish(),
+    }
+}
+
+/// Deterministic pseudorandom generator, for a given seed it always produces the same values
+///
+/// It's a SplitMix64 generator, simple and fast, but not cryptographically secure.
+#[derive(Clone, Debug)]
+pub struct SeededRng(u64);
+
+impl SeededRng {
+    /// Creates generator from seed
+    pub fn new(seed: u64) -> Self {
+        SeededRng(seed)
+    }
+
+    /// Returns next value
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns next value in range [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        to_f64(self.next_u64())
+    }
+}
+
+fn to_f64(value: u64) -> f64 {
+    (value >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Type, which can be randomly generated by [SeededRng](struct.SeededRng.html)
+///
+/// Integers and `bool` are uniformly distributed over all values, floats over range [0, 1).
+pub trait Random {
+    /// Generates a random value
+    fn random(rng: &mut SeededRng) -> Self;
+}
+
+macro_rules! impl_random_for_ints {
+    ($($int:ty),*) => {
+        $(
+            impl Random for $int {
+                fn random(rng: &mut SeededRng) -> Self {
+                    rng.next_u64() as $int
+                }
+            }
+        )*
+    }
+}
+
+impl_random_for_ints!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+impl Random for u128 {
+    fn random(rng: &mut SeededRng) -> Self {
+        (u128::from(rng.next_u64()) << 64) | u128::from(rng.next_u64())
+    }
+}
+
+impl Random for i128 {
+    fn random(rng: &mut SeededRng) -> Self {
+        u128::random(rng) as i128
+    }
+}
+
+impl Random for bool {
+    fn random(rng: &mut SeededRng) -> Self {
+        rng.next_u64() >> 63 == 1
+    }
+}
+
+impl Random for f64 {
+    fn random(rng: &mut SeededRng) -> Self {
+        rng.next_f64()
+    }
+}
+
+impl Random for f32 {
+    fn random(rng: &mut SeededRng) -> Self {
+        (rng.next_u64() >> 40) as f32 / (1u32 << 24) as f32
+    }
+}
+
+/// Trait for making random number sources deterministic
+///
+/// The trait is implemented for all functions, so it can be used with [random_u64](fn.random_u64.html),
+/// [random_f64](fn.random_f64.html) and any function of tested code designated as its random entry point.
+/// [mock_sequence](#tymethod.mock_sequence) sets up a mock with [mock_safe](../mocking/trait.Mockable.html#tymethod.mock_safe)
+/// and [mock_seeded](#tymethod.mock_seeded) with [mock_scoped](../mocking/trait.Mockable.html#tymethod.mock_scoped).
+///
+/// Note: methods have any effect only if called on functions [annotated as mockable](https://docs.rs/mocktopus_macros).
+pub trait MockableRandom<T: Tuple, O> {
+    /// Makes function return given values in order
+    ///
+    /// When all values are used, subsequent calls panic.
+    ///
+    /// ```
+    /// #[mockable]
+    /// fn roll_die() -> u8 { ... }
+    ///
+    /// #[test]
+    /// fn play_test() {
+    ///     roll_die.mock_sequence(vec![6, 6, 1]);
+    ///
+    ///     assert_eq!(Outcome::Lost, play());
+    /// }
+    /// ```
+    fn mock_sequence<I>(&self, values: I) where I: IntoIterator<Item=O>, I::IntoIter: 'static;
+
+    /// Makes function return values generated by [SeededRng](struct.SeededRng.html) created from `seed`
+    ///
+    /// The mock is removed when the returned guard is dropped. If it's dropped while the thread is panicking,
+    /// e.g. because an assertion failed, the seed is printed to stderr, so the failure can be replayed
+    /// by setting `MOCKTOPUS_SEED` when seed comes from [test_seed](fn.test_seed.html).
+    ///
+    /// ```
+    /// #[test]
+    /// fn backoff_test() {
+    ///     let _seed = random::random_f64.mock_seeded(random::test_seed());
+    ///
+    ///     for attempt in 0..10 {
+    ///         assert!(backoff(attempt) <= Duration::from_secs(60));
+    ///     }
+    /// }
+    /// ```
+    fn mock_seeded(&self, seed: u64) -> MockGuard where O: Random;
+}
+
+impl<T: Tuple, O: 'static, F: FnOnce<T, Output=O>> MockableRandom<T, O> for F {
+    fn mock_sequence<I>(&self, values: I) where I: IntoIterator<Item=O>, I::IntoIter: 'static {
+        let mut values = values.into_iter();
+        let mut used = 0;
+        self.mock_safe(GeneratorMock::new(move || match values.next() {
+            Some(value) => {
+                used += 1;
+                Ok(value)
+            },
+            None => Err(format!("sequence of {} random values is exhausted", used)),
+        }, None))
+    }
+
+    fn mock_seeded(&self, seed: u64) -> MockGuard where O: Random {
+        let mut rng = SeededRng::new(seed);
+        let reporter = SeedReporter::new(any::type_name::<F>(), seed, "returned random values generated");
+        self.mock_scoped(GeneratorMock::new(move || Ok(O::random(&mut rng)), Some(reporter)))
+    }
+}
+
+/// Mock closure ignoring arguments and returning generated values, panics with message returned as error
+///
+/// The reporter is dropped together with the mock, so the seed is reported when the mock is removed during panic.
+struct GeneratorMock<G> {
+    generate: G,
+    _reporter: Option<SeedReporter>,
+}
+
+impl<G> GeneratorMock<G> {
+    fn new(generate: G, reporter: Option<SeedReporter>) -> Self {
+        GeneratorMock { generate, _reporter: reporter }
+    }
+}
+
+impl<T: Tuple, O, G: FnMut() -> Result<O, String>> FnOnce<T> for GeneratorMock<G> {
+    type Output = MockResult<T, O>;
+
+    extern "rust-call" fn call_once(mut self, args: T) -> MockResult<T, O> {
+        self.call_mut(args)
+    }
+}
+
+impl<T: Tuple, O, G: FnMut() -> Result<O, String>> FnMut<T> for GeneratorMock<G> {
+    extern "rust-call" fn call_mut(&mut self, _: T) -> MockResult<T, O> {
+        match (self.generate)() {
+            Ok(value) => MockResult::Return(value),
+            Err(message) => MockResult::Panic(message),
+        }
+    }
+}
+
+/// Prints seed of a mock to stderr if dropped while the thread is panicking, e.g. because an assertion failed
+#[derive(Debug)]
+pub(crate) struct SeedReporter {
+    fn_name: &'static str,
+    seed: u64,
+    seeded_behavior: &'static str,
+}
+
+impl SeedReporter {
+    /// Creates reporter of mock of `fn_name`, which `seeded_behavior` with `seed`
+    pub(crate) fn new(fn_name: &'static str, seed: u64, seeded_behavior: &'static str) -> Self {
+        SeedReporter { fn_name, seed, seeded_behavior }
+    }
+}
+
+impl Drop for SeedReporter {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            eprintln!("mock of {} {} with seed {}, set {}={} to replay",
+                      self.fn_name, self.seeded_behavior, self.seed, SEED_ENV_VAR, self.seed);
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use crate::mocking::{FakeMocks, Mockable, MockResult};
+use mocktopus_macros::mockable;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Mockable wrapper of [Instant::now](https://doc.rust-lang.org/std/time/struct.Instant.html#method.now)
+#[mockable(mocktopus = "crate")]
+pub fn instant_now() -> Instant {
+    Instant::now()
+}
+
+/// Mockable wrapper of [SystemTime::now](https://doc.rust-lang.org/std/time/struct.SystemTime.html#method.now)
+#[mockable(mocktopus = "crate")]
+pub fn system_time_now() -> SystemTime {
+    SystemTime::now()
+}
+
+/// Mockable wrapper of [thread::sleep](https://doc.rust-lang.org/std/thread/fn.sleep.html)
+#[mockable(mocktopus = "crate")]
+pub fn sleep(duration: Duration) {
+    thread::sleep(duration)
+}
+
+/// Clock controlled by test, which replaces real time in current thread
+///
+/// Creating clock mocks [instant_now](fn.instant_now.html), [system_time_now](fn.system_time_now.html) and
+/// [sleep](fn.sleep.html). The time doesn't pass on its own, it's advanced by hand or by calls to `sleep`,
+/// which return immediately. Once the clock is dropped, the functions are back to what they were before.
+///
+/// ```
+/// fn is_expired(created: Instant) -> bool {
+///     time::instant_now() - created > Duration::from_secs(60)
+/// }
+///
+/// #[test]
+/// fn is_expired_test() {
+///     let clock = FakeClock::new();
+///     let created = time::instant_now();
+///
+///     clock.advance(Duration::from_secs(61));
+///
+///     assert!(is_expired(created));
+/// }
+/// ```
+pub struct FakeClock {
+    elapsed: Rc<Cell<Duration>>,
+    _mocks: FakeMocks,
+}
+
+impl FakeClock {
+    /// Creates clock starting at current real time
+    pub fn new() -> Self {
+        Self::starting_at(SystemTime::now())
+    }
+
+    /// Creates clock, which reports `system_time` as current system time
+    pub fn starting_at(system_time: SystemTime) -> Self {
+        let start_instant = Instant::now();
+        let elapsed = Rc::new(Cell::new(Duration::from_secs(0)));
+        let instant_elapsed = elapsed.clone();
+        let system_time_elapsed = elapsed.clone();
+        let sleep_elapsed = elapsed.clone();
+        let mocks = FakeMocks::new(vec![
+            instant_now.mock_scoped(move || MockResult::Return(start_instant + instant_elapsed.get())),
+            system_time_now.mock_scoped(move || MockResult::Return(system_time + system_time_elapsed.get())),
+            sleep.mock_scoped(move |duration| {
+                sleep_elapsed.set(sleep_elapsed.get() + duration);
+                MockResult::Return(())
+            }),
+        ]);
+        FakeClock {
+            elapsed,
+            _mocks: mocks,
+        }
+    }
+
+    /// Moves clock forward
+    pub fn advance(&self, duration: Duration) {
+        self.elapsed.set(self.elapsed.get() + duration);
+    }
+
+    /// Time passed since clock creation
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed.get()
+    }
+}
+
+impl Default for FakeClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,10 @@
+use mocktopus::macros::*;
+
+#[mockable]
+fn greet(name: &str) -> String {
+    name.to_string()
+}
+
+fn main() {
+    __mock_greet::returns("mocked");
+}
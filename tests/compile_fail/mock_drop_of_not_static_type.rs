@@ -0,0 +1,18 @@
+use mocktopus::macros::*;
+use mocktopus::mocking::*;
+
+struct Struct<'a>(&'a str);
+
+#[mockable]
+impl<'a> Drop for Struct<'a> {
+    fn drop(&mut self) {}
+}
+
+fn mock_drop_of<'a>(_: &Struct<'a>) {
+    mock_drop(|_: &mut Struct<'a>| MockResult::Return(()));
+}
+
+fn main() {
+    let value = String::new();
+    mock_drop_of(&Struct(&value));
+}
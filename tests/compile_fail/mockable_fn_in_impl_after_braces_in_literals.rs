@@ -0,0 +1,23 @@
+use mocktopus::macros::*;
+
+#[mockable]
+fn standalone() -> char {
+    let _ = r#"impl Struct { "#;
+    let _ = "} trait Trait {";
+    /* impl Struct { /* } */ */
+    '{'
+}
+
+struct Struct<'a>(&'a str);
+
+impl<'a> Struct<'a> {
+    #[mockable]
+    fn function() -> char {
+        '}'
+    }
+}
+
+fn main() {
+    standalone();
+    Struct::function();
+}
@@ -0,0 +1,14 @@
+use mocktopus::macros::*;
+
+struct Struct;
+
+impl Struct {
+    #[mockable]
+    fn function() -> u32 {
+        1
+    }
+}
+
+fn main() {
+    Struct::function();
+}
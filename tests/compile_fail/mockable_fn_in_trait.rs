@@ -0,0 +1,10 @@
+use mocktopus::macros::*;
+
+trait Trait {
+    #[mockable]
+    fn function() -> u32 {
+        1
+    }
+}
+
+fn main() {}
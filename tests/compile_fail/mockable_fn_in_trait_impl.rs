@@ -0,0 +1,18 @@
+use mocktopus::macros::*;
+
+trait Trait {
+    fn function() -> u32;
+}
+
+struct Struct;
+
+impl Trait for Struct {
+    #[mockable]
+    fn function() -> u32 {
+        1
+    }
+}
+
+fn main() {
+    Struct::function();
+}
@@ -0,0 +1,14 @@
+use mocktopus::macros::*;
+
+struct Struct;
+
+impl Struct {
+    #[mockable]
+    fn method(&self) -> u32 {
+        1
+    }
+}
+
+fn main() {
+    Struct.method();
+}
@@ -0,0 +1,7 @@
+use mocktopus::macros::*;
+
+#[mockable_static]
+#[no_mangle]
+static VALUE: u32 = 1;
+
+fn main() {}
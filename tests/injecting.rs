@@ -1,4 +1,6 @@
-#![feature(const_fn, proc_macro_hygiene)]
+#![feature(proc_macro_hygiene)]
+// Modules are nested in modules of the same name to test injection into them
+#![allow(clippy::module_inception)]
 
 // Test if injecting works even if mocktopus is aliased
 extern crate mocktopus as mocktopus_aliased;
@@ -87,6 +89,93 @@ mod injector_injects_annotated_traits {
     }
 }
 
+mod injector_injects_annotated_extern_blocks {
+    use super::*;
+
+    #[mockable(safe)]
+    extern "C" {
+        fn abs(value: i32) -> i32;
+        #[link_name = "labs"]
+        pub fn long_abs(_: i64) -> i64;
+        fn printf(format: *const u8, ...) -> i32;
+    }
+
+    #[test]
+    fn when_not_mocked_then_runs_extern_fns() {
+        assert_eq!(1, abs(-1));
+        assert_eq!(2, long_abs(-2));
+        assert_eq!(0, unsafe { printf(b"\0".as_ptr()) });
+    }
+
+    #[test]
+    fn when_mocked_then_runs_mock() {
+        abs.mock_safe(|value| MockResult::Return(value * 10));
+        long_abs.mock_safe(|value| MockResult::Continue((value * 10,)));
+
+        assert_eq!(-10, abs(-1));
+        assert_eq!(20, long_abs(-2));
+    }
+
+    mod when_in_annotated_module {
+        use super::*;
+
+        #[mockable(only(abs), safe)]
+        mod module {
+            extern "C" {
+                pub fn abs(value: i32) -> i32;
+                pub fn labs(value: i64) -> i64;
+            }
+        }
+
+        #[test]
+        fn then_injects_selected_extern_fns() {
+            module::abs.mock_safe(|value| MockResult::Return(value * 10));
+
+            assert_eq!(-10, module::abs(-1));
+            assert_eq!(2, unsafe { module::labs(-2) });
+        }
+    }
+
+    mod when_unlinked {
+        use super::*;
+
+        #[mockable(safe, unlinked)]
+        #[link(name = "mocktopus_not_existing_library")]
+        extern "C" {
+            fn not_existing(value: i32) -> i32;
+        }
+
+        #[test]
+        #[should_panic(expected = "extern function not_existing was called, but it's not mocked")]
+        fn when_not_mocked_then_panics() {
+            not_existing(1);
+        }
+
+        #[test]
+        fn when_mocked_then_runs_mock() {
+            not_existing.mock_safe(|value| MockResult::Return(value * 10));
+
+            assert_eq!(10, not_existing(1));
+        }
+    }
+
+    mod when_not_safe {
+        use super::*;
+
+        #[mockable]
+        extern "C" {
+            fn abs(value: i32) -> i32;
+        }
+
+        #[test]
+        fn then_does_not_inject_extern_fns() {
+            assert_eq!(1, unsafe { abs(-1) });
+        }
+
+        // Trait Mockable is not implemented for unsafe functions
+    }
+}
+
 #[mockable]
 mod module_annotated_declaration;
 
@@ -219,10 +308,9 @@ mod injector_injects_annotated_items {
         }
 
         #[test]
-        fn when_mocked_then_runs_normally() {
+        #[should_panic(expected = "function isn't mockable")]
+        fn when_mocked_then_panics() {
             module_annotated_declaration::function.mock_safe(|| MockResult::Return("mocked"));
-
-            assert_eq!("not mocked", module_annotated_declaration::function());
         }
     }
 }
@@ -490,12 +578,11 @@ mod injector_does_not_inject_not_mockable_items {
         }
 
         #[test]
-        fn when_mocked_then_runs_normally() {
+        #[should_panic(expected = "not_mocked_fn isn't mockable")]
+        fn when_mocked_then_panics() {
             unsafe {
                 mocked_mod::not_mocked_fn.mock_raw(|| MockResult::Return("mocked"));
             }
-
-            assert_eq!("not mocked", mocked_mod::not_mocked_fn());
         }
     }
 
@@ -522,12 +609,11 @@ mod injector_does_not_inject_not_mockable_items {
         }
 
         #[test]
-        fn when_mocked_then_runs_normally() {
+        #[should_panic(expected = "not_mocked_fn isn't mockable")]
+        fn when_mocked_then_panics() {
             unsafe {
                 MockedStruct::not_mocked_fn.mock_raw(|| MockResult::Return("mocked"))
             }
-
-            assert_eq!("not mocked", MockedStruct::not_mocked_fn());
         }
     }
 
@@ -589,12 +675,233 @@ mod injector_does_not_inject_not_mockable_items {
         }
 
         #[test]
-        fn when_mocked_then_runs_normally() {
+        #[should_panic(expected = "not_mocked_fn isn't mockable")]
+        fn when_mocked_then_panics() {
             unsafe {
                 mocked_mod::mocked_mod::not_mocked_fn.mock_raw(|| MockResult::Return("mocked"))
             }
+        }
+    }
+}
 
-            assert_eq!("not mocked", mocked_mod::mocked_mod::not_mocked_fn());
+mod injector_injects_only_fns_selected_by_arguments {
+    use super::*;
+
+    mod injects_only_listed_fns {
+        use super::*;
+
+        #[mockable(only(mocked_fn_1, mocked_fn_2))]
+        mod mocked_mod {
+            pub fn mocked_fn_1() -> &'static str {
+                "not mocked"
+            }
+
+            pub fn mocked_fn_2() -> &'static str {
+                "not mocked"
+            }
+
+            pub fn not_mocked_fn() -> &'static str {
+                "not mocked"
+            }
+        }
+
+        #[test]
+        fn when_mocked_then_runs_mock_only_for_listed_fns() {
+            mocked_mod::mocked_fn_1.mock_safe(|| MockResult::Return("mocked"));
+            mocked_mod::mocked_fn_2.mock_safe(|| MockResult::Return("mocked"));
+
+            assert_eq!("mocked", mocked_mod::mocked_fn_1());
+            assert_eq!("mocked", mocked_mod::mocked_fn_2());
+            assert_eq!("not mocked", mocked_mod::not_mocked_fn());
+        }
+
+        #[test]
+        #[should_panic(expected = "not_mocked_fn isn't mockable")]
+        fn when_not_listed_fn_mocked_then_panics() {
+            mocked_mod::not_mocked_fn.mock_safe(|| MockResult::Return("mocked"));
+        }
+    }
+
+    mod injects_all_fns_except_listed {
+        use super::*;
+
+        struct Struct;
+
+        #[mockable(except(not_mocked_fn))]
+        impl Struct {
+            fn mocked_fn() -> &'static str {
+                "not mocked"
+            }
+
+            fn not_mocked_fn() -> &'static str {
+                "not mocked"
+            }
+        }
+
+        #[test]
+        fn when_mocked_then_runs_mock_only_for_not_listed_fns() {
+            Struct::mocked_fn.mock_safe(|| MockResult::Return("mocked"));
+
+            assert_eq!("mocked", Struct::mocked_fn());
+            assert_eq!("not mocked", Struct::not_mocked_fn());
+        }
+
+        #[test]
+        #[should_panic(expected = "not_mocked_fn isn't mockable")]
+        fn when_listed_fn_mocked_then_panics() {
+            Struct::not_mocked_fn.mock_safe(|| MockResult::Return("mocked"));
+        }
+    }
+
+    mod injects_only_pub_fns {
+        use super::*;
+
+        struct Struct;
+
+        #[mockable(pub_only)]
+        mod mocked_mod {
+            use super::*;
+
+            pub fn mocked_fn() -> &'static str {
+                "not mocked"
+            }
+
+            pub(crate) fn not_mocked_fn() -> &'static str {
+                "not mocked"
+            }
+
+            impl Struct {
+                pub fn mocked_method() -> &'static str {
+                    "not mocked"
+                }
+
+                pub(super) fn not_mocked_method() -> &'static str {
+                    "not mocked"
+                }
+            }
+        }
+
+        #[test]
+        fn when_mocked_then_runs_mock_only_for_pub_fns() {
+            mocked_mod::mocked_fn.mock_safe(|| MockResult::Return("mocked"));
+            Struct::mocked_method.mock_safe(|| MockResult::Return("mocked"));
+
+            assert_eq!("mocked", mocked_mod::mocked_fn());
+            assert_eq!("not mocked", mocked_mod::not_mocked_fn());
+            assert_eq!("mocked", Struct::mocked_method());
+            assert_eq!("not mocked", Struct::not_mocked_method());
+        }
+
+        #[test]
+        #[should_panic(expected = "not_mocked_fn isn't mockable")]
+        fn when_not_pub_fn_mocked_then_panics() {
+            mocked_mod::not_mocked_fn.mock_safe(|| MockResult::Return("mocked"));
+        }
+
+        #[test]
+        #[should_panic(expected = "not_mocked_method isn't mockable")]
+        fn when_not_pub_method_mocked_then_panics() {
+            Struct::not_mocked_method.mock_safe(|| MockResult::Return("mocked"));
+        }
+    }
+
+    mod injects_only_fns_with_names_matching_pattern {
+        use super::*;
+
+        #[mockable(name = "db_*")]
+        mod mocked_mod {
+            pub fn db_get() -> &'static str {
+                "not mocked"
+            }
+
+            pub fn db_put() -> &'static str {
+                "not mocked"
+            }
+
+            pub fn cache_get() -> &'static str {
+                "not mocked"
+            }
+        }
+
+        #[test]
+        fn when_mocked_then_runs_mock_only_for_matching_fns() {
+            mocked_mod::db_get.mock_safe(|| MockResult::Return("mocked"));
+            mocked_mod::db_put.mock_safe(|| MockResult::Return("mocked"));
+
+            assert_eq!("mocked", mocked_mod::db_get());
+            assert_eq!("mocked", mocked_mod::db_put());
+            assert_eq!("not mocked", mocked_mod::cache_get());
+        }
+
+        #[test]
+        #[should_panic(expected = "cache_get isn't mockable")]
+        fn when_not_matching_fn_mocked_then_panics() {
+            mocked_mod::cache_get.mock_safe(|| MockResult::Return("mocked"));
+        }
+    }
+
+    mod injects_fns_using_given_crate_path {
+        use super::*;
+
+        mod reexported {
+            pub(super) use crate::mocktopus_aliased as mocktopus;
+        }
+
+        #[mockable(mocktopus = "self::reexported::mocktopus")]
+        fn function() -> &'static str {
+            "not mocked"
+        }
+
+        struct Struct;
+
+        #[mockable(mocktopus = "crate::mocktopus_aliased")]
+        impl Drop for Struct {
+            fn drop(&mut self) {}
+        }
+
+        #[test]
+        fn when_mocked_then_runs_mock() {
+            function.mock_safe(|| MockResult::Return("mocked"));
+
+            assert_eq!("mocked", function());
+        }
+
+        #[test]
+        fn when_drop_mocked_then_runs_mock() {
+            let observer = crate::mocktopus_aliased::mocking_utils::DropObserver::<Struct>::new();
+
+            drop(Struct);
+
+            assert_eq!(1, observer.drop_count());
+        }
+    }
+
+    mod nested_annotation_overrides_arguments {
+        use super::*;
+
+        #[mockable(only(mocked_fn))]
+        mod mocked_mod {
+            use super::*;
+
+            pub fn mocked_fn() -> &'static str {
+                "not mocked"
+            }
+
+            #[mockable]
+            pub mod nested_mod {
+                pub fn nested_mocked_fn() -> &'static str {
+                    "not mocked"
+                }
+            }
+        }
+
+        #[test]
+        fn when_mocked_then_runs_mock_for_nested_annotation_fns() {
+            mocked_mod::mocked_fn.mock_safe(|| MockResult::Return("mocked"));
+            mocked_mod::nested_mod::nested_mocked_fn.mock_safe(|| MockResult::Return("mocked"));
+
+            assert_eq!("mocked", mocked_mod::mocked_fn());
+            assert_eq!("mocked", mocked_mod::nested_mod::nested_mocked_fn());
         }
     }
 }
@@ -613,12 +920,11 @@ mod injector_ignores_const_fns {
     }
 
     #[test]
-    fn when_mocked_then_returns_1() {
+    #[should_panic(expected = "const_fn isn't mockable")]
+    fn when_mocked_then_panics() {
         unsafe {
             const_fn.mock_raw(|| MockResult::Return(2));
         }
-
-        assert_eq!(1, const_fn());
     }
 }
 
@@ -678,12 +984,11 @@ mod injector_does_not_inject_macro_generated_fns {
     }
 
     #[test]
-    fn when_mocked_then_returns_1() {
+    #[should_panic(expected = "macro_generated_fn isn't mockable")]
+    fn when_mocked_then_panics() {
         unsafe {
             macro_generated_fn.mock_raw(|| MockResult::Return(2));
         }
-
-        assert_eq!(1, macro_generated_fn());
     }
 }
 
@@ -907,7 +1212,7 @@ mod injecting_trait_default_method_with_unused_generic_param {
     }
 }
 
-mod injecting_structs_with_drop_does_nothing {
+mod injecting_structs_with_drop {
     use super::*;
     use std::mem::drop;
 
@@ -941,6 +1246,7 @@ mod injecting_lifetimed_fn_of_same_lifetimed_trait_impl_of_same_lifetimed_struct
         fn function(arg: &'a str) -> &'a str;
     }
 
+    #[allow(dead_code)]
     struct Struct<'a>(&'a str);
 
     #[mockable]
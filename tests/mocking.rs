@@ -1,14 +1,36 @@
+#[macro_use]
+extern crate lazy_static;
+#[macro_use]
 extern crate mocktopus;
 
+mod mocking_companions;
+mod mocking_env;
+mod mocking_expectations;
+mod mocking_faults;
 mod mocking_fns;
+mod mocking_fs;
+mod mocking_impl_traits;
 mod mocking_methods;
+mod mocking_net;
+mod mocking_panics;
+mod mocking_process;
+mod mocking_random;
+mod mocking_refs;
+mod mocking_rules;
+mod mocking_statics;
 mod mocking_trait_defaults;
+mod mocking_trait_mocks;
+mod mocking_time;
 mod mocking_traits;
+mod mocking_with_mocks;
+mod mocking_wrappers;
 
 use mocktopus::macros::*;
 use mocktopus::mocking::*;
 use mocktopus::mocking_utils::*;
+use std::cell::RefCell;
 use std::fmt::Display;
+use std::rc::Rc;
 
 mod mock_safe {
     use super::*;
@@ -31,6 +53,54 @@ mod mock_safe {
     }
 }
 
+mod mock_scoped {
+    use super::*;
+
+    #[mockable]
+    pub fn function() -> &'static str {
+        "not mocked"
+    }
+
+    #[test]
+    fn when_guard_alive_then_returns_mocked() {
+        let _guard = function.mock_scoped(|| MockResult::Return("mocked"));
+
+        assert_eq!("mocked", function());
+    }
+
+    #[test]
+    fn when_guard_dropped_then_runs_normally() {
+        let guard = function.mock_scoped(|| MockResult::Return("mocked"));
+
+        drop(guard);
+
+        assert_eq!("not mocked", function());
+    }
+
+    #[test]
+    fn when_guard_dropped_then_restores_previous_mock() {
+        function.mock_safe(|| MockResult::Return("mocked 1"));
+        let guard = function.mock_scoped(|| MockResult::Return("mocked 2"));
+
+        assert_eq!("mocked 2", function());
+        drop(guard);
+
+        assert_eq!("mocked 1", function());
+    }
+
+    #[test]
+    fn when_nested_guards_dropped_then_restores_mocks_in_order() {
+        let guard_1 = function.mock_scoped(|| MockResult::Return("mocked 1"));
+        let guard_2 = function.mock_scoped(|| MockResult::Return("mocked 2"));
+
+        assert_eq!("mocked 2", function());
+        drop(guard_2);
+        assert_eq!("mocked 1", function());
+        drop(guard_1);
+        assert_eq!("not mocked", function());
+    }
+}
+
 mod mocks_do_not_leak_between_tests {
     use super::*;
 
@@ -80,6 +150,8 @@ mod panicking_inside_mock_is_safe {
     }
 }
 
+// References are passed on purpose, generic function is mocked for reference types
+#[allow(clippy::needless_borrows_for_generic_args)]
 mod mocking_generic_over_a_type_with_lifetime_mocks_all_lifetime_variants {
     use super::*;
     use std::fmt::Display;
@@ -104,6 +176,8 @@ mod mocking_generic_over_a_type_with_lifetime_mocks_all_lifetime_variants {
     }
 }
 
+// References are passed on purpose, generic function is mocked for reference types
+#[allow(clippy::needless_borrows_for_generic_args)]
 mod mocking_generic_over_a_reference_does_not_mock_opposite_mutability_variant {
     use super::*;
     use std::fmt::Display;
@@ -346,3 +420,149 @@ mod clear_mocks {
         assert_eq!("not mocked 2", mockable_2());
     }
 }
+
+mod mocking_drop {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[allow(dead_code)]
+    struct Struct(Rc<Cell<&'static str>>, Rc<()>);
+
+    #[mockable]
+    impl Drop for Struct {
+        fn drop(&mut self) {
+            self.0.set("dropped")
+        }
+    }
+
+    struct Guard;
+
+    #[mockable]
+    impl Drop for Guard {
+        fn drop(&mut self) {
+
+        }
+    }
+
+    struct GenericStruct<T>(Rc<Cell<&'static str>>, T);
+
+    #[mockable]
+    impl<T> Drop for GenericStruct<T> {
+        fn drop(&mut self) {
+            self.0.set("dropped")
+        }
+    }
+
+    fn new_state() -> Rc<Cell<&'static str>> {
+        Rc::new(Cell::new("not dropped"))
+    }
+
+    #[test]
+    fn when_not_mocked_then_runs_normally() {
+        let state = new_state();
+
+        drop(Struct(state.clone(), Rc::new(())));
+
+        assert_eq!("dropped", state.get());
+    }
+
+    #[test]
+    fn when_mocked_continue_then_runs_mock_and_drop_body() {
+        let state = new_state();
+        mock_drop(|dropped: &mut Struct| {
+            assert_eq!("not dropped", dropped.0.get());
+            dropped.0.set("mocked");
+            MockResult::Continue(())
+        });
+
+        drop(Struct(state.clone(), Rc::new(())));
+
+        assert_eq!("dropped", state.get());
+    }
+
+    #[test]
+    fn when_mocked_return_then_skips_drop_body_but_drops_fields() {
+        let state = new_state();
+        let field = Rc::new(());
+        mock_drop(|dropped: &mut Struct| {
+            dropped.0.set("mocked");
+            MockResult::Return(())
+        });
+
+        drop(Struct(state.clone(), field.clone()));
+
+        assert_eq!("mocked", state.get());
+        assert_eq!(1, Rc::strong_count(&field));
+    }
+
+    #[test]
+    #[should_panic(expected = "failed to close")]
+    fn when_mock_panics_then_drop_panics() {
+        mock_drop(|_: &mut Struct| panic!("failed to close"));
+
+        drop(Struct(new_state(), Rc::new(())));
+    }
+
+    #[test]
+    fn when_impl_generic_then_drop_is_not_mocked() {
+        let state = new_state();
+        mock_drop(|_: &mut GenericStruct<u32>| MockResult::Return(()));
+
+        drop(GenericStruct(state.clone(), 1u32));
+
+        assert_eq!("dropped", state.get());
+    }
+
+    #[test]
+    fn when_dropping_same_type_inside_mock_then_runs_it_normally() {
+        let state = new_state();
+        mock_drop(|dropped: &mut Struct| {
+            let inner_state = new_state();
+            drop(Struct(inner_state.clone(), Rc::new(())));
+            dropped.0.set(inner_state.get());
+            MockResult::Return(())
+        });
+
+        drop(Struct(state.clone(), Rc::new(())));
+
+        assert_eq!("dropped", state.get());
+    }
+
+    #[test]
+    fn when_mocks_cleared_then_runs_normally() {
+        let state = new_state();
+        mock_drop(|_: &mut Struct| MockResult::Return(()));
+
+        clear_mocks();
+        drop(Struct(state.clone(), Rc::new(())));
+
+        assert_eq!("dropped", state.get());
+    }
+
+    #[test]
+    fn when_observed_then_counts_drops() {
+        let observer = DropObserver::<Guard>::new();
+
+        let guard = Guard;
+        assert_eq!(0, observer.drop_count());
+        drop(guard);
+        drop(Guard);
+
+        assert_eq!(2, observer.drop_count());
+    }
+
+    #[test]
+    fn when_observer_dropped_then_restores_previous_mock() {
+        let state = new_state();
+        mock_drop(|dropped: &mut Struct| {
+            dropped.0.set("mocked");
+            MockResult::Return(())
+        });
+
+        drop(DropObserver::<Struct>::new());
+        drop(Struct(state.clone(), Rc::new(())));
+
+        assert_eq!("mocked", state.get());
+    }
+}
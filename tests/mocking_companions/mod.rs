@@ -0,0 +1,6 @@
+use super::*;
+
+mod when_calls_set;
+mod when_expect_set;
+mod when_function_has_cfg;
+mod when_returns_set;
@@ -0,0 +1,78 @@
+use super::*;
+use std::borrow::Cow;
+
+#[mockable]
+fn greet(name: &str, times: u32) -> String {
+    name.repeat(times as usize)
+}
+
+#[mockable]
+fn first_word(text: &str) -> &str {
+    text.split(' ').next().unwrap_or("")
+}
+
+#[mockable]
+fn constant() -> u32 {
+    1
+}
+
+#[mockable]
+fn unnamed(_: u32, b: u32) -> u32 {
+    b
+}
+
+#[mockable]
+fn normalize(text: Cow<str>) -> Cow<str> {
+    text
+}
+
+mod nested {
+    use super::*;
+
+    #[mockable]
+    pub fn function(arg: u32) -> u32 {
+        arg
+    }
+}
+
+#[test]
+fn then_mock_receives_named_arguments() {
+    __mock_greet::calls(|args| format!("{} x{}", args.name, args.times));
+
+    assert_eq!("hello x3", greet("hello", 3));
+}
+
+#[test]
+fn and_function_returns_elided_reference_then_mock_can_return_argument() {
+    __mock_first_word::calls(|args| &args.text[1..]);
+
+    assert_eq!("ello world", first_word("hello world"));
+}
+
+#[test]
+fn and_function_has_no_arguments_then_mock_is_called() {
+    __mock_constant::calls(|_| 2);
+
+    assert_eq!(2, constant());
+}
+
+#[test]
+fn and_arguments_are_not_identifiers_then_fields_are_named_by_position() {
+    __mock_unnamed::calls(|args| args.arg0 * args.b);
+
+    assert_eq!(6, unnamed(2, 3));
+}
+
+#[test]
+fn and_argument_has_elided_lifetime_in_path_then_mock_receives_it() {
+    __mock_normalize::calls(|args| Cow::Owned(args.text.to_uppercase()));
+
+    assert_eq!("TEXT", normalize(Cow::Borrowed("text")));
+}
+
+#[test]
+fn and_function_is_in_module_then_companion_is_next_to_it() {
+    nested::__mock_function::calls(|args| args.arg * 2);
+
+    assert_eq!(4, nested::function(2));
+}
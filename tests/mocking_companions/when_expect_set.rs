@@ -0,0 +1,24 @@
+use super::*;
+
+#[mockable]
+fn greet(name: &str, times: u32) -> String {
+    name.repeat(times as usize)
+}
+
+#[test]
+fn and_met_then_verification_passes() {
+    __mock_greet::expect(2, |args| args.name.to_string());
+
+    assert_eq!("a", greet("a", 5));
+    assert_eq!("b", greet("b", 5));
+
+    verify_expectations();
+}
+
+#[test]
+#[should_panic(expected = "to be called 1 times, but it was called 0 times")]
+fn and_not_met_then_verification_panics() {
+    __mock_greet::expect(1, |args| args.name.to_string());
+
+    verify_expectations();
+}
@@ -0,0 +1,21 @@
+use super::*;
+
+#[mockable]
+#[cfg(not(test))]
+fn configured_out() -> u32 {
+    1
+}
+
+#[mockable]
+#[cfg(test)]
+#[cfg_attr(test, inline)]
+fn configured_in() -> u32 {
+    1
+}
+
+#[test]
+fn then_companion_exists_only_with_function() {
+    __mock_configured_in::returns(2);
+
+    assert_eq!(2, configured_in());
+}
@@ -0,0 +1,26 @@
+use super::*;
+
+#[mockable]
+fn greet(name: &str, times: u32) -> String {
+    name.repeat(times as usize)
+}
+
+#[mockable]
+fn first_word(text: &str) -> &str {
+    text.split(' ').next().unwrap_or("")
+}
+
+#[test]
+fn then_returns_value_on_every_call() {
+    __mock_greet::returns("mocked".to_string());
+
+    assert_eq!("mocked", greet("a", 1));
+    assert_eq!("mocked", greet("b", 2));
+}
+
+#[test]
+fn and_function_returns_elided_reference_then_returns_static_value() {
+    __mock_first_word::returns("mocked");
+
+    assert_eq!("mocked", first_word("hello world"));
+}
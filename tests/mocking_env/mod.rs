@@ -0,0 +1,3 @@
+use super::*;
+
+mod when_env_fns_called;
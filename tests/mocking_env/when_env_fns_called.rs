@@ -0,0 +1,53 @@
+use super::*;
+use mocktopus::env;
+use std::env::VarError;
+use std::path::PathBuf;
+
+#[test]
+fn and_not_mocked_then_returns_real_values() {
+    std::env::set_var("MOCKTOPUS_TEST_ENV_REAL", "real");
+
+    assert_eq!(Ok("real".to_string()), env::var("MOCKTOPUS_TEST_ENV_REAL"));
+    assert!(env::vars().contains(&("MOCKTOPUS_TEST_ENV_REAL".to_string(), "real".to_string())));
+    assert_eq!(std::env::current_dir().unwrap(), env::current_dir().unwrap());
+}
+
+#[test]
+fn and_var_mocked_then_returns_mocked_values() {
+    env::var.mock_safe(|key| MockResult::Return(match key {
+        "HOME" => Ok("/mocked".to_string()),
+        _ => Err(VarError::NotPresent),
+    }));
+
+    assert_eq!(Ok("/mocked".to_string()), env::var("HOME"));
+    assert_eq!(Err(VarError::NotPresent), env::var("PATH"));
+}
+
+#[test]
+fn and_vars_mocked_then_returns_mocked_values() {
+    env::vars.mock_safe(|| MockResult::Return(vec![("KEY".to_string(), "value".to_string())]));
+
+    assert_eq!(vec![("KEY".to_string(), "value".to_string())], env::vars());
+}
+
+#[test]
+fn and_set_var_mocked_then_does_not_change_environment() {
+    let set_vars = Rc::new(RefCell::new(Vec::new()));
+    let mock_set_vars = set_vars.clone();
+    env::set_var.mock_safe(move |key, value| {
+        mock_set_vars.borrow_mut().push((key.to_string(), value.to_string()));
+        MockResult::Return(())
+    });
+
+    env::set_var("MOCKTOPUS_TEST_ENV_SET", "value");
+
+    assert_eq!(vec![("MOCKTOPUS_TEST_ENV_SET".to_string(), "value".to_string())], *set_vars.borrow());
+    assert!(std::env::var("MOCKTOPUS_TEST_ENV_SET").is_err());
+}
+
+#[test]
+fn and_current_dir_mocked_then_returns_mocked_value() {
+    env::current_dir.mock_safe(|| MockResult::Return(Ok(PathBuf::from("/mocked"))));
+
+    assert_eq!(PathBuf::from("/mocked"), env::current_dir().unwrap());
+}
@@ -0,0 +1,6 @@
+use super::*;
+
+mod when_mock_expected;
+mod when_mocks_verified;
+mod when_test_attribute_used;
+mod when_test_run;
@@ -0,0 +1,54 @@
+use super::*;
+use std::panic;
+
+#[mockable]
+fn function(arg: u32) -> u32 {
+    arg
+}
+
+#[test]
+fn and_expected_calls_made_then_verification_passes() {
+    function.mock_expect(2, |a| MockResult::Return(a + 1));
+
+    assert_eq!(2, function(1));
+    assert_eq!(3, function(2));
+
+    verify_expectations();
+}
+
+#[test]
+#[should_panic(expected = "to be called 2 times, but it was called 1 times")]
+fn and_too_few_calls_made_then_verification_panics() {
+    function.mock_expect(2, |a| MockResult::Continue((a,)));
+
+    function(1);
+
+    verify_expectations();
+}
+
+#[test]
+#[should_panic(expected = "to be called 0 times, but it was called 1 times")]
+fn and_too_many_calls_made_then_verification_panics() {
+    function.mock_expect(0, |a| MockResult::Continue((a,)));
+
+    function(1);
+
+    verify_expectations();
+}
+
+#[test]
+fn and_verified_then_expectations_are_removed() {
+    function.mock_expect(1, |a| MockResult::Continue((a,)));
+    let _ = panic::catch_unwind(verify_expectations);
+
+    verify_expectations();
+}
+
+#[test]
+fn and_mocks_cleared_then_expectations_are_removed() {
+    function.mock_expect(1, |a| MockResult::Continue((a,)));
+
+    clear_mocks();
+
+    verify_expectations();
+}
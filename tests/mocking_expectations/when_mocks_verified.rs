@@ -0,0 +1,76 @@
+use super::*;
+
+#[mockable]
+fn function(arg: u32) -> u32 {
+    arg
+}
+
+fn not_mockable(arg: u32) -> u32 {
+    arg
+}
+
+fn not_mockable_generic<T>(arg: T) -> T {
+    arg
+}
+
+#[test]
+fn and_mock_called_then_verification_passes() {
+    function.mock_safe(|a| MockResult::Continue((a,)));
+
+    function(1);
+
+    verify_mocks_called();
+}
+
+#[test]
+#[should_panic(expected = "mocking::mocking_expectations::when_mocks_verified::not_mockable isn't mockable, \
+                           so its mock would never be called. Annotate it with #[mockable]")]
+fn and_function_not_mockable_then_mocking_panics() {
+    not_mockable.mock_safe(|_| MockResult::Return(0));
+}
+
+#[test]
+#[should_panic(expected = "mock of mocking::mocking_expectations::when_mocks_verified::not_mockable_generic<u32> was never called")]
+fn and_generic_function_not_mockable_then_verification_panics() {
+    not_mockable_generic::<u32>.mock_safe(|_| MockResult::Return(0));
+
+    assert_eq!(1, not_mockable_generic(1u32));
+
+    verify_mocks_called();
+}
+
+#[test]
+#[should_panic(expected = "mock of mocking::mocking_expectations::when_mocks_verified::function was never called")]
+fn and_mock_not_called_then_verification_panics() {
+    function.mock_safe(|_| MockResult::Return(0));
+
+    verify_mocks_called();
+}
+
+#[test]
+#[should_panic(expected = "mock of mocking::mocking_expectations::when_mocks_verified::function was never called")]
+fn and_scoped_mock_dropped_without_call_then_verification_panics() {
+    drop(function.mock_scoped(|_| MockResult::Return(0)));
+
+    verify_mocks_called();
+}
+
+#[test]
+fn and_mock_replaced_after_call_then_verification_passes() {
+    function.mock_safe(|_| MockResult::Return(0));
+    function(1);
+    let guard = function.mock_scoped(|_| MockResult::Return(2));
+
+    drop(guard);
+
+    verify_mocks_called();
+}
+
+#[test]
+fn and_mocks_cleared_then_they_are_not_verified() {
+    function.mock_safe(|_| MockResult::Return(0));
+
+    clear_mocks();
+
+    verify_mocks_called();
+}
@@ -0,0 +1,48 @@
+use super::*;
+
+#[mockable]
+fn function(arg: u32) -> u32 {
+    arg
+}
+
+fn not_mockable_generic<T>(arg: T) -> T {
+    arg
+}
+
+#[mocktopus::mocktopus_test]
+fn and_expectation_met_then_passes() {
+    function.mock_expect(1, |_| MockResult::Return(0));
+
+    assert_eq!(0, function(1));
+}
+
+#[mocktopus::mocktopus_test]
+#[should_panic(expected = "to be called 1 times, but it was called 0 times")]
+fn and_expectation_not_met_then_fails() {
+    function.mock_expect(1, |_| MockResult::Return(0));
+}
+
+#[mocktopus::mocktopus_test(strict)]
+fn and_strict_and_mocks_called_then_passes() {
+    function.mock_safe(|_| MockResult::Return(0));
+
+    assert_eq!(0, function(1));
+}
+
+#[mocktopus::mocktopus_test(strict)]
+#[should_panic(expected = "not_mockable_generic<u32> was never called")]
+fn and_strict_and_generic_function_not_mockable_then_fails() {
+    not_mockable_generic::<u32>.mock_safe(|_| MockResult::Return(0));
+
+    not_mockable_generic(1u32);
+}
+
+#[mocktopus::mocktopus_test]
+fn and_returns_result_then_question_mark_works() -> Result<(), String> {
+    function.mock_safe(|_| MockResult::Return(0));
+
+    let value: u32 = "0".parse().map_err(|_| "not a number".to_string())?;
+
+    assert_eq!(value, function(1));
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use super::*;
+use std::panic::{self, AssertUnwindSafe};
+
+#[mockable]
+fn function(arg: u32) -> u32 {
+    arg
+}
+
+#[test]
+fn then_mocks_are_cleared_before_test() {
+    function.mock_safe(|_| MockResult::Return(0));
+
+    run_test(|| assert_eq!(1, function(1)));
+}
+
+#[test]
+fn and_test_panics_then_mocks_are_cleared_after_test() {
+    let result = panic::catch_unwind(|| run_test(|| {
+        function.mock_safe(|_| MockResult::Return(0));
+        panic!("test failed");
+    }));
+
+    assert!(result.is_err());
+    assert_eq!(1, function(1));
+}
+
+#[test]
+fn and_expectation_not_met_then_panics_and_clears_mocks() {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| run_test(|| {
+        function.mock_expect(1, |_| MockResult::Return(0));
+    })));
+
+    assert!(result.is_err());
+    assert_eq!(1, function(1));
+    verify_expectations();
+}
+
+#[test]
+fn and_test_returns_then_returns_its_result() {
+    assert_eq!("result", run_test(|| "result"));
+}
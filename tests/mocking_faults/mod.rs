@@ -0,0 +1,5 @@
+use super::*;
+
+mod when_calls_after_n_failing;
+mod when_calls_failing_with_probability;
+mod when_nth_call_failing;
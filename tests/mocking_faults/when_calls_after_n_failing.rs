@@ -0,0 +1,44 @@
+use super::*;
+use mocktopus::faults::*;
+
+#[mockable]
+fn function(arg: u32) -> Result<u32, String> {
+    Ok(arg)
+}
+
+fn call_many(count: u32) -> Vec<Result<u32, String>> {
+    (1..=count).map(function).collect()
+}
+
+#[test]
+fn and_called_then_fails_all_calls_after_n() {
+    function.fail_after(2, || "mocked".to_string());
+
+    assert_eq!(vec![Ok(1), Ok(2), Err("mocked".to_string()), Err("mocked".to_string())], call_many(4));
+}
+
+#[test]
+fn and_zero_then_fails_all_calls() {
+    function.fail_after(0, || "mocked".to_string());
+
+    assert_eq!(vec![Err("mocked".to_string()), Err("mocked".to_string())], call_many(2));
+}
+
+#[test]
+fn and_error_closure_has_state_then_creates_errors_with_it() {
+    let mut error_count = 0;
+    function.fail_after(1, move || {
+        error_count += 1;
+        format!("mocked {}", error_count)
+    });
+
+    assert_eq!(vec![Ok(1), Err("mocked 1".to_string()), Err("mocked 2".to_string())], call_many(3));
+}
+
+#[test]
+fn and_mock_safe_set_then_it_replaces_failures() {
+    function.fail_after(0, || "mocked".to_string());
+    function.mock_safe(|_| MockResult::Return(Ok(0)));
+
+    assert_eq!(Ok(0), function(1));
+}
@@ -0,0 +1,86 @@
+use super::*;
+use mocktopus::faults::*;
+use std::panic;
+
+#[mockable]
+fn function(arg: u32) -> Result<u32, String> {
+    Ok(arg)
+}
+
+fn call_many(count: u32) -> Vec<Result<u32, String>> {
+    (1..=count).map(function).collect()
+}
+
+#[test]
+fn and_probability_is_zero_then_never_fails() {
+    let _guard = function.fail_with_probability(0., 1, || "mocked".to_string());
+
+    assert!(call_many(100).iter().all(Result::is_ok));
+}
+
+#[test]
+fn and_probability_is_one_then_always_fails() {
+    let _guard = function.fail_with_probability(1., 1, || "mocked".to_string());
+
+    assert!(call_many(100).iter().all(Result::is_err));
+}
+
+#[test]
+#[should_panic(expected = "probability must be between 0 and 1, got 1.5")]
+fn and_probability_is_above_one_then_panics() {
+    let _guard = function.fail_with_probability(1.5, 1, || "mocked".to_string());
+}
+
+#[test]
+#[should_panic(expected = "probability must be between 0 and 1, got NaN")]
+fn and_probability_is_nan_then_panics() {
+    let _guard = function.fail_with_probability(f64::NAN, 1, || "mocked".to_string());
+}
+
+#[test]
+fn and_probability_is_half_then_fails_some_calls() {
+    let _guard = function.fail_with_probability(0.5, 1, || "mocked".to_string());
+
+    let failures = call_many(1000).iter().filter(|result| result.is_err()).count();
+
+    assert!(failures > 400 && failures < 600, "failures: {}", failures);
+}
+
+#[test]
+fn and_seed_is_same_then_fails_same_calls() {
+    let first_guard = function.fail_with_probability(0.5, 7, || "mocked".to_string());
+    let first = call_many(100);
+    drop(first_guard);
+    let _guard = function.fail_with_probability(0.5, 7, || "mocked".to_string());
+
+    assert_eq!(first, call_many(100));
+}
+
+#[test]
+fn and_seed_is_different_then_fails_different_calls() {
+    let first_guard = function.fail_with_probability(0.5, 7, || "mocked".to_string());
+    let first = call_many(100);
+    drop(first_guard);
+    let _guard = function.fail_with_probability(0.5, 8, || "mocked".to_string());
+
+    assert_ne!(first, call_many(100));
+}
+
+#[test]
+fn and_guard_dropped_then_stops_failing() {
+    let guard = function.fail_with_probability(1., 1, || "mocked".to_string());
+
+    drop(guard);
+
+    assert!(call_many(100).iter().all(Result::is_ok));
+}
+
+#[test]
+fn and_guard_dropped_during_panic_then_panic_propagates() {
+    let result = panic::catch_unwind(|| {
+        let _guard = function.fail_with_probability(1., 1, || "mocked".to_string());
+        panic!("assertion failed");
+    });
+
+    assert_eq!("assertion failed", *result.unwrap_err().downcast_ref::<&str>().unwrap());
+}
@@ -0,0 +1,31 @@
+use super::*;
+use mocktopus::faults::*;
+
+#[mockable]
+fn function(arg: u32) -> Result<u32, String> {
+    Ok(arg)
+}
+
+#[mockable]
+fn optional(arg: u32) -> Option<u32> {
+    Some(arg)
+}
+
+fn call_many(count: u32) -> Vec<Result<u32, String>> {
+    (1..=count).map(function).collect()
+}
+
+#[test]
+fn and_called_then_fails_only_nth_call() {
+    function.fail_nth(2, || "mocked".to_string());
+
+    assert_eq!(vec![Ok(1), Err("mocked".to_string()), Ok(3), Ok(4)], call_many(4));
+}
+
+#[test]
+fn and_fn_returns_option_then_returns_none() {
+    optional.fail_nth(1, || ());
+
+    assert_eq!(None, optional(1));
+    assert_eq!(Some(2), optional(2));
+}
@@ -1,4 +1,5 @@
 use super::*;
 
+mod when_fn_const_generic;
 mod when_fn_generic;
 mod when_fn_regular;
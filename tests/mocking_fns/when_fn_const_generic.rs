@@ -0,0 +1,75 @@
+use super::*;
+
+#[mockable]
+fn function<const N: usize>(arg: bool) -> String {
+    format!("{} {}", arg, N)
+}
+
+#[test]
+fn and_not_mocked_then_runs_normally() {
+    assert_eq!("true 1", function::<1>(true));
+    assert_eq!("true 2", function::<2>(true));
+}
+
+#[test]
+fn and_continue_mocked_then_runs_with_modified_args_for_mocked_value_only() {
+    unsafe {
+        function::<1>.mock_raw(|a| MockResult::Continue((!a,)));
+    }
+
+    assert_eq!("false 1", function::<1>(true));
+    assert_eq!("true 2", function::<2>(true));
+}
+
+#[test]
+fn and_return_mocked_then_returns_mocking_result_for_mocked_value_only() {
+    unsafe {
+        function::<1>.mock_raw(|a| MockResult::Return(format!("mocked {}", a)));
+    }
+
+    assert_eq!("mocked true", function::<1>(true));
+    assert_eq!("true 2", function::<2>(true));
+}
+
+mod and_fn_has_type_and_const_generics {
+    use super::*;
+
+    #[mockable]
+    fn function<T: Display, const N: usize>(arg: T) -> String {
+        format!("{} {}", arg, N)
+    }
+
+    #[test]
+    fn and_return_mocked_then_returns_mocking_result_for_mocked_type_and_value_only() {
+        unsafe {
+            function::<u32, 1>.mock_raw(|a| MockResult::Return(format!("mocked {}", a)));
+        }
+
+        assert_eq!("mocked 5", function::<u32, 1>(5));
+        assert_eq!("5 2", function::<u32, 2>(5));
+        assert_eq!("5 1", function::<i32, 1>(5));
+    }
+}
+
+mod and_fn_is_in_const_generic_struct {
+    use super::*;
+
+    struct Struct<const N: usize>;
+
+    #[mockable]
+    impl<const N: usize> Struct<N> {
+        fn method(&self, arg: bool) -> String {
+            format!("{} {}", arg, N)
+        }
+    }
+
+    #[test]
+    fn and_return_mocked_then_returns_mocking_result_for_mocked_value_only() {
+        unsafe {
+            Struct::<1>::method.mock_raw(|_, a| MockResult::Return(format!("mocked {}", a)));
+        }
+
+        assert_eq!("mocked true", Struct::<1>.method(true));
+        assert_eq!("true 2", Struct::<2>.method(true));
+    }
+}
@@ -0,0 +1,4 @@
+use super::*;
+
+mod when_fs_fn_mocked;
+mod when_in_memory_fs_used;
@@ -0,0 +1,10 @@
+use super::*;
+use mocktopus::fs;
+use std::path::Path;
+
+#[test]
+fn then_returns_mocked_contents() {
+    fs::read.mock_safe(|_| MockResult::Return(Ok(b"mocked".to_vec())));
+
+    assert_eq!(b"mocked".to_vec(), fs::read(Path::new("/not/existing")).unwrap());
+}
@@ -0,0 +1,123 @@
+use super::*;
+use mocktopus::fs::{self, EntryMetadata, File, InMemoryFs};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+fn error_kind<T>(result: io::Result<T>) -> io::ErrorKind {
+    match result {
+        Ok(_) => panic!("operation succeeded"),
+        Err(error) => error.kind(),
+    }
+}
+
+#[test]
+fn and_file_added_then_can_be_read() {
+    let in_memory_fs = InMemoryFs::new();
+    in_memory_fs.add_file("/dir/file", "contents");
+
+    assert_eq!(b"contents".to_vec(), fs::read(Path::new("/dir/file")).unwrap());
+    assert_eq!(EntryMetadata::file(8), fs::entry_metadata(Path::new("/dir/file")).unwrap());
+    assert_eq!(EntryMetadata::dir(), fs::entry_metadata(Path::new("/dir")).unwrap());
+}
+
+#[test]
+fn and_file_missing_then_read_fails_with_not_found() {
+    let _in_memory_fs = InMemoryFs::new();
+
+    assert_eq!(io::ErrorKind::NotFound, error_kind(fs::read(Path::new("/file"))));
+    assert_eq!(io::ErrorKind::NotFound, error_kind(fs::entry_metadata(Path::new("/file"))));
+    assert_eq!(io::ErrorKind::NotFound, error_kind(File::open(Path::new("/file"))));
+}
+
+#[test]
+fn and_file_written_then_it_is_stored() {
+    let in_memory_fs = InMemoryFs::new();
+
+    fs::write(Path::new("file"), b"contents").unwrap();
+
+    assert_eq!(Some(b"contents".to_vec()), in_memory_fs.file("file"));
+}
+
+#[test]
+fn and_file_written_to_missing_dir_then_fails_with_not_found() {
+    let _in_memory_fs = InMemoryFs::new();
+
+    assert_eq!(io::ErrorKind::NotFound, error_kind(fs::write(Path::new("/dir/file"), b"contents")));
+}
+
+#[test]
+fn and_dirs_created_then_file_can_be_written_in_them() {
+    let in_memory_fs = InMemoryFs::new();
+
+    fs::create_dir_all(Path::new("/a/b")).unwrap();
+    fs::write(Path::new("/a/b/file"), b"contents").unwrap();
+
+    assert!(in_memory_fs.has_dir("/a"));
+    assert!(in_memory_fs.has_dir("/a/b"));
+    assert_eq!(Some(b"contents".to_vec()), in_memory_fs.file("/a/b/file"));
+}
+
+#[test]
+fn and_dir_read_then_returns_direct_entries() {
+    let in_memory_fs = InMemoryFs::new();
+    in_memory_fs.add_file("/dir/file", "");
+    in_memory_fs.add_file("/dir/nested/file", "");
+    in_memory_fs.add_file("/other", "");
+
+    let mut entries = fs::read_dir_paths(Path::new("/dir")).unwrap();
+    entries.sort();
+
+    assert_eq!(vec![PathBuf::from("/dir/file"), PathBuf::from("/dir/nested")], entries);
+}
+
+#[test]
+fn and_file_removed_then_it_is_missing() {
+    let in_memory_fs = InMemoryFs::new();
+    in_memory_fs.add_file("/file", "");
+
+    fs::remove_file(Path::new("/file")).unwrap();
+
+    assert_eq!(None, in_memory_fs.file("/file"));
+    assert_eq!(io::ErrorKind::NotFound, error_kind(fs::remove_file(Path::new("/file"))));
+}
+
+#[test]
+fn and_file_opened_then_contents_can_be_read() {
+    let in_memory_fs = InMemoryFs::new();
+    in_memory_fs.add_file("/file", "contents");
+    let mut contents = String::new();
+
+    File::open(Path::new("/file")).unwrap().read_to_string(&mut contents).unwrap();
+
+    assert_eq!("contents", contents);
+}
+
+#[test]
+fn and_error_injected_then_operations_on_path_fail() {
+    let in_memory_fs = InMemoryFs::new();
+    in_memory_fs.add_file("/secret", "contents");
+    in_memory_fs.add_file("/other", "contents");
+    in_memory_fs.fail_with("/secret", io::ErrorKind::PermissionDenied);
+
+    assert_eq!(io::ErrorKind::PermissionDenied, error_kind(fs::read(Path::new("/secret"))));
+    assert_eq!(io::ErrorKind::PermissionDenied, error_kind(fs::write(Path::new("/secret"), b"")));
+    assert_eq!(io::ErrorKind::PermissionDenied, error_kind(File::open(Path::new("/secret"))));
+    assert_eq!(b"contents".to_vec(), fs::read(Path::new("/other")).unwrap());
+}
+
+#[test]
+fn and_dropped_then_real_fs_is_used() {
+    let in_memory_fs = InMemoryFs::new();
+    in_memory_fs.add_file("Cargo.toml", "mocked");
+
+    drop(in_memory_fs);
+
+    assert_ne!(b"mocked".to_vec(), fs::read(Path::new("Cargo.toml")).unwrap());
+}
+
+#[test]
+fn and_dropped_without_calls_then_mocks_verification_passes() {
+    drop(InMemoryFs::new());
+
+    verify_mocks_called();
+}
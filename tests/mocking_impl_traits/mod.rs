@@ -0,0 +1,4 @@
+use super::*;
+
+mod when_impl_trait_in_args;
+mod when_impl_trait_returned;
@@ -0,0 +1,70 @@
+use super::*;
+
+#[mockable]
+fn function(arg: impl Display) -> String {
+    format!("not mocked {}", arg)
+}
+
+#[mockable]
+fn nested(arg_1: &impl Display, arg_2: Vec<impl Display>) -> String {
+    format!("not mocked {} {}", arg_1, arg_2.len())
+}
+
+#[mockable]
+fn generic<T: Display>(arg_1: T, arg_2: impl Display) -> String {
+    format!("not mocked {} {}", arg_1, arg_2)
+}
+
+struct Struct;
+
+#[mockable]
+impl Struct {
+    fn method(&self, arg: impl Display) -> String {
+        format!("not mocked {}", arg)
+    }
+}
+
+#[test]
+fn and_not_mocked_then_runs_normally() {
+    assert_eq!("not mocked 1", function(1));
+    assert_eq!("not mocked 1 2", nested(&1, vec!['a', 'b']));
+    assert_eq!("not mocked 1 a", generic(1, 'a'));
+    assert_eq!("not mocked 1", Struct.method(1));
+}
+
+#[test]
+fn and_called_with_turbofish_then_runs_normally() {
+    assert_eq!("not mocked 1 a", generic::<u32>(1, 'a'));
+}
+
+#[test]
+fn and_mocked_then_runs_mock_for_mocked_types() {
+    function.mock_safe(|arg: u32| MockResult::Return(format!("mocked {}", arg)));
+
+    assert_eq!("mocked 1", function(1u32));
+    assert_eq!("not mocked 1", function(1i32));
+}
+
+#[test]
+fn and_nested_mocked_then_runs_mock_for_mocked_types() {
+    nested.mock_safe(|arg_1: &u32, _: Vec<char>| MockResult::Return(format!("mocked {}", arg_1)));
+
+    assert_eq!("mocked 1", nested(&1u32, vec!['a']));
+    assert_eq!("not mocked 1 1", nested(&1u32, vec!["a"]));
+}
+
+#[test]
+fn and_generic_mocked_then_runs_mock_for_mocked_types() {
+    generic::<u32>.mock_safe(|arg_1, arg_2: char| MockResult::Return(format!("mocked {} {}", arg_1, arg_2)));
+
+    assert_eq!("mocked 1 a", generic(1u32, 'a'));
+    assert_eq!("not mocked 1 a", generic(1u32, "a"));
+}
+
+#[test]
+fn and_method_mocked_then_runs_mock_for_mocked_types() {
+    Struct::method.mock_safe(|_, arg: u32| MockResult::Return(format!("mocked {}", arg)));
+
+    assert_eq!("mocked 1", Struct.method(1u32));
+    assert_eq!("not mocked 1", Struct.method(1i32));
+}
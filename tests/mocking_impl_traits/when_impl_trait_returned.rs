@@ -0,0 +1,56 @@
+use super::*;
+
+use std::vec::IntoIter;
+
+#[mockable]
+fn function(count: u32) -> impl Iterator<Item = u32> {
+    (0..count).collect::<Vec<_>>().into_iter()
+}
+
+#[mockable]
+fn boxed(count: u32) -> impl Iterator<Item = u32> {
+    Box::new(0..count) as Box<dyn Iterator<Item = u32>>
+}
+
+#[test]
+fn and_not_mocked_then_runs_normally() {
+    assert_eq!(vec![0, 1], function(2).collect::<Vec<_>>());
+    assert_eq!(vec![0, 1], boxed(2).collect::<Vec<_>>());
+}
+
+#[test]
+fn and_mocked_with_concrete_type_then_returns_mock() {
+    function.mock_impl_trait(|count| MockResult::Return(vec![count; 3].into_iter()));
+
+    assert_eq!(vec![2, 2, 2], function(2).collect::<Vec<_>>());
+}
+
+#[test]
+fn and_mocked_with_boxed_type_then_returns_mock() {
+    boxed.mock_impl_trait(|count| MockResult::Return(Box::new(Some(count).into_iter()) as Box<dyn Iterator<Item = u32>>));
+
+    assert_eq!(vec![2], boxed(2).collect::<Vec<_>>());
+}
+
+#[test]
+fn and_mocked_continue_then_runs_with_modified_args() {
+    function.mock_impl_trait(|count| MockResult::Continue::<_, IntoIter<u32>>((count + 1,)));
+
+    assert_eq!(vec![0, 1, 2], function(2).collect::<Vec<_>>());
+}
+
+#[test]
+fn and_mocked_with_mock_safe_then_its_mock_takes_precedence() {
+    function.mock_impl_trait(|_| MockResult::Return(Vec::<u32>::new().into_iter()));
+    function.mock_safe(|count| MockResult::Continue((count + 1,)));
+
+    assert_eq!(Vec::<u32>::new(), function(2).collect::<Vec<_>>());
+}
+
+#[test]
+#[should_panic(expected = "IntoIter<u32>, but the function returns ")]
+fn and_mocked_with_wrong_type_then_panics() {
+    function.mock_impl_trait(|count| MockResult::Return(Some(count).into_iter()));
+
+    let _ = function(2);
+}
@@ -0,0 +1,5 @@
+use super::*;
+
+mod when_loopback_net_tcp_used;
+mod when_loopback_net_udp_used;
+mod when_net_fn_mocked;
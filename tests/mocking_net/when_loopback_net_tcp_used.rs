@@ -0,0 +1,96 @@
+use super::*;
+use mocktopus::net::{LoopbackNet, TcpStream};
+use std::io::{self, Read, Write};
+
+fn error_kind<T>(result: io::Result<T>) -> io::ErrorKind {
+    match result {
+        Ok(_) => panic!("operation succeeded"),
+        Err(error) => error.kind(),
+    }
+}
+
+#[test]
+fn and_peer_scripted_then_connection_exchanges_bytes() {
+    let net = LoopbackNet::new();
+    let peer = net.tcp_peer("server:1234").send("hello ").send(b"world".to_vec());
+
+    let mut stream = TcpStream::connect("server:1234").unwrap();
+    stream.write_all(b"request").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    assert_eq!("hello world", response);
+    assert_eq!(b"request".to_vec(), peer.received());
+    assert_eq!(1, peer.connection_count());
+}
+
+#[test]
+fn and_connected_many_times_then_each_connection_gets_all_bytes() {
+    let net = LoopbackNet::new();
+    let peer = net.tcp_peer("server:1234").send("hi");
+
+    for message in &["a", "b"] {
+        let mut stream = TcpStream::connect("server:1234").unwrap();
+        stream.write_all(message.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert_eq!("hi", response);
+    }
+
+    assert_eq!(b"ab".to_vec(), peer.received());
+    assert_eq!(2, peer.connection_count());
+}
+
+#[test]
+fn and_peer_not_scripted_then_connection_refused() {
+    let _net = LoopbackNet::new();
+
+    assert_eq!(io::ErrorKind::ConnectionRefused, error_kind(TcpStream::connect("server:1234")));
+}
+
+#[test]
+fn and_peer_refuses_then_connection_refused() {
+    let net = LoopbackNet::new();
+    let peer = net.tcp_peer("server:1234").refuse();
+
+    assert_eq!(io::ErrorKind::ConnectionRefused, error_kind(TcpStream::connect("server:1234")));
+    assert_eq!(0, peer.connection_count());
+}
+
+#[test]
+fn and_reset_after_read_then_reads_fail_after_limit() {
+    let net = LoopbackNet::new();
+    net.tcp_peer("server:1234").send("hello").reset_after_read(3);
+    let mut stream = TcpStream::connect("server:1234").unwrap();
+    let mut buf = [0; 10];
+
+    assert_eq!(3, stream.read(&mut buf).unwrap());
+    assert_eq!(b"hel", &buf[..3]);
+    assert_eq!(io::ErrorKind::ConnectionReset, error_kind(stream.read(&mut buf)));
+    assert_eq!(io::ErrorKind::ConnectionReset, error_kind(stream.write(b"x")));
+}
+
+#[test]
+fn and_reset_after_write_then_writes_fail_after_limit() {
+    let net = LoopbackNet::new();
+    let peer = net.tcp_peer("server:1234").send("hello").reset_after_write(4);
+    let mut stream = TcpStream::connect("server:1234").unwrap();
+
+    assert_eq!(io::ErrorKind::ConnectionReset, error_kind(stream.write_all(b"request")));
+    assert_eq!(b"requ".to_vec(), peer.received());
+    assert_eq!(io::ErrorKind::ConnectionReset, error_kind(stream.read(&mut [0; 10])));
+}
+
+#[test]
+fn and_net_dropped_then_previous_mock_restored_and_connections_keep_working() {
+    TcpStream::connect.mock_safe(|_| MockResult::Return(Err(io::Error::new(io::ErrorKind::TimedOut, "mocked"))));
+    let net = LoopbackNet::new();
+    let peer = net.tcp_peer("server:1234");
+    let mut stream = TcpStream::connect("server:1234").unwrap();
+
+    drop(net);
+
+    assert_eq!(io::ErrorKind::TimedOut, error_kind(TcpStream::connect("server:1234")));
+    stream.write_all(b"after drop").unwrap();
+    assert_eq!(b"after drop".to_vec(), peer.received());
+}
@@ -0,0 +1,64 @@
+use super::*;
+use mocktopus::net::{LoopbackNet, UdpSocket};
+use std::io;
+use std::net::SocketAddr;
+
+fn error_kind<T>(result: io::Result<T>) -> io::ErrorKind {
+    match result {
+        Ok(_) => panic!("operation succeeded"),
+        Err(error) => error.kind(),
+    }
+}
+
+fn socket_addr(addr: &str) -> SocketAddr {
+    addr.parse().unwrap()
+}
+
+#[test]
+fn and_peer_scripted_then_replies_to_datagrams() {
+    let net = LoopbackNet::new();
+    let peer = net.udp_peer(socket_addr("10.0.0.1:53")).reply("first").reply("second");
+    let socket = UdpSocket::bind(socket_addr("0.0.0.0:5000")).unwrap();
+    let mut buf = [0; 10];
+
+    assert_eq!(4, socket.send_to(b"ping", socket_addr("10.0.0.1:53")).unwrap());
+    assert_eq!((5, socket_addr("10.0.0.1:53")), socket.recv_from(&mut buf).unwrap());
+    assert_eq!(b"first", &buf[..5]);
+    socket.send_to(b"pong", socket_addr("10.0.0.1:53")).unwrap();
+    assert_eq!((6, socket_addr("10.0.0.1:53")), socket.recv_from(&mut buf).unwrap());
+    assert_eq!(b"second", &buf[..6]);
+    assert_eq!(vec![b"ping".to_vec(), b"pong".to_vec()], peer.received());
+}
+
+#[test]
+fn and_replies_run_out_then_receiving_would_block() {
+    let net = LoopbackNet::new();
+    net.udp_peer(socket_addr("10.0.0.1:53"));
+    let socket = UdpSocket::bind(socket_addr("0.0.0.0:5000")).unwrap();
+
+    socket.send_to(b"ping", socket_addr("10.0.0.1:53")).unwrap();
+
+    assert_eq!(io::ErrorKind::WouldBlock, error_kind(socket.recv_from(&mut [0; 10])));
+}
+
+#[test]
+fn and_peer_not_scripted_then_datagram_is_lost() {
+    let _net = LoopbackNet::new();
+    let socket = UdpSocket::bind(socket_addr("0.0.0.0:5000")).unwrap();
+
+    assert_eq!(4, socket.send_to(b"ping", socket_addr("10.0.0.1:53")).unwrap());
+    assert_eq!(io::ErrorKind::WouldBlock, error_kind(socket.recv_from(&mut [0; 10])));
+}
+
+#[test]
+fn and_buffer_too_small_then_datagram_is_truncated() {
+    let net = LoopbackNet::new();
+    net.udp_peer(socket_addr("10.0.0.1:53")).reply("long reply");
+    let socket = UdpSocket::bind(socket_addr("0.0.0.0:5000")).unwrap();
+    let mut buf = [0; 4];
+
+    socket.send_to(b"ping", socket_addr("10.0.0.1:53")).unwrap();
+
+    assert_eq!((4, socket_addr("10.0.0.1:53")), socket.recv_from(&mut buf).unwrap());
+    assert_eq!(b"long", &buf);
+}
@@ -0,0 +1,17 @@
+use super::*;
+use mocktopus::net::TcpStream;
+use std::io;
+
+fn error_kind<T>(result: io::Result<T>) -> io::ErrorKind {
+    match result {
+        Ok(_) => panic!("operation succeeded"),
+        Err(error) => error.kind(),
+    }
+}
+
+#[test]
+fn then_returns_mocked_result() {
+    TcpStream::connect.mock_safe(|addr| MockResult::Return(Err(io::Error::new(io::ErrorKind::TimedOut, addr))));
+
+    assert_eq!(io::ErrorKind::TimedOut, error_kind(TcpStream::connect("server:1234")));
+}
@@ -0,0 +1,6 @@
+use super::*;
+
+mod when_drop_panic_mocked;
+mod when_panic_mocked;
+mod when_trait_method_panic_mocked;
+mod when_unwind_mocked;
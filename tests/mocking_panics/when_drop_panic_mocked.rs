@@ -0,0 +1,24 @@
+use super::*;
+use std::panic::{self, AssertUnwindSafe};
+
+fn catch_panic_message<F: FnOnce() -> R, R>(f: F) -> String {
+    let payload = panic::catch_unwind(AssertUnwindSafe(f)).err().expect("did not panic");
+    *payload.downcast::<String>().expect("payload is not a String")
+}
+
+struct Struct;
+
+#[mockable]
+impl Drop for Struct {
+    fn drop(&mut self) {}
+}
+
+#[test]
+fn then_panics_with_message_naming_dropped_type() {
+    mock_drop(|_: &mut Struct| MockResult::Panic("failure".to_string()));
+
+    let message = catch_panic_message(|| drop(Struct));
+
+    assert!(message.starts_with("mock of drop of "), "message: {}", message);
+    assert!(message.ends_with("Struct panicked on call 1: failure"), "message: {}", message);
+}
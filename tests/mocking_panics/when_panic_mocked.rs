@@ -0,0 +1,95 @@
+use super::*;
+use std::cell::Cell;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Once;
+
+#[mockable]
+fn function(arg: u32) -> u32 {
+    arg
+}
+
+const LOCATED_FUNCTION_LINE: u32 = line!() + 1;
+#[mockable] fn located_function() {}
+
+thread_local! {
+    static PANIC_LOCATION: Cell<Option<(String, u32)>> = const { Cell::new(None) };
+}
+
+/// Returns location of the last panic of the current thread, which ran panic hook
+fn catch_panic_location<F: FnOnce() -> R, R>(f: F) -> (String, u32) {
+    static HOOK_INSTALLED: Once = Once::new();
+    HOOK_INSTALLED.call_once(|| {
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let location = info.location().map(|location| (location.file().to_string(), location.line()));
+            PANIC_LOCATION.with(|panic_location| panic_location.set(location));
+            default_hook(info)
+        }));
+    });
+    PANIC_LOCATION.with(|panic_location| panic_location.set(None));
+    let _ = panic::catch_unwind(AssertUnwindSafe(f));
+    PANIC_LOCATION.with(Cell::take).expect("did not panic")
+}
+
+fn catch_panic_message<F: FnOnce() -> R, R>(f: F) -> String {
+    let payload = panic::catch_unwind(AssertUnwindSafe(f)).err().expect("did not panic");
+    *payload.downcast::<String>().expect("payload is not a String")
+}
+
+#[test]
+#[should_panic(expected = "mocking_panics::when_panic_mocked::function panicked on call 1: mocked failure")]
+fn then_panics_with_message_naming_function() {
+    function.mock_safe(|_| MockResult::Panic("mocked failure".to_string()));
+
+    function(1);
+}
+
+#[test]
+fn then_panic_location_is_in_mocked_function() {
+    located_function.mock_safe(|| MockResult::Panic("failure".to_string()));
+
+    let (file, line) = catch_panic_location(located_function);
+
+    assert!(file.ends_with("mocking_panics/when_panic_mocked.rs"), "file: {}", file);
+    assert_eq!(LOCATED_FUNCTION_LINE, line);
+}
+
+#[test]
+fn then_message_contains_call_number() {
+    function.mock_safe(|arg| match arg {
+        2 => MockResult::Panic("failure".to_string()),
+        _ => MockResult::Continue((arg,)),
+    });
+
+    assert_eq!(1, function(1));
+    let message = catch_panic_message(|| function(2));
+
+    assert!(message.ends_with("function panicked on call 2: failure"), "message: {}", message);
+}
+
+#[test]
+fn then_call_number_is_counted_since_mock_was_set() {
+    function.mock_safe(|arg| MockResult::Continue((arg,)));
+    function(1);
+    function.mock_safe(|_| MockResult::Panic("failure".to_string()));
+
+    let message = catch_panic_message(|| function(1));
+
+    assert!(message.ends_with("function panicked on call 1: failure"), "message: {}", message);
+}
+
+#[test]
+fn then_function_can_be_called_after_panic_caught() {
+    let mut calls = 0;
+    function.mock_safe(move |arg| {
+        calls += 1;
+        match calls {
+            1 => MockResult::Panic("failure".to_string()),
+            _ => MockResult::Return(arg * 2),
+        }
+    });
+
+    catch_panic_message(|| function(1));
+
+    assert_eq!(4, function(2));
+}
@@ -0,0 +1,30 @@
+use super::*;
+use std::panic::{self, AssertUnwindSafe};
+
+fn catch_panic_message<F: FnOnce() -> R, R>(f: F) -> String {
+    let payload = panic::catch_unwind(AssertUnwindSafe(f)).err().expect("did not panic");
+    *payload.downcast::<String>().expect("payload is not a String")
+}
+
+#[mockable]
+trait Trait {
+    fn method(&self) -> u32;
+}
+
+struct Struct;
+
+#[mockable]
+impl Trait for Struct {
+    fn method(&self) -> u32 {
+        1
+    }
+}
+
+#[test]
+fn then_panics_with_message_naming_method() {
+    mock_trait_method!(Trait::method, |_: TraitImplementor| MockResult::Panic::<(), u32>("failure".to_string()));
+
+    let message = catch_panic_message(|| Struct.method());
+
+    assert_eq!("mock of Trait::method panicked on call 1: failure", message);
+}
@@ -0,0 +1,19 @@
+use super::*;
+use std::panic;
+
+#[mockable]
+fn function(arg: u32) -> u32 {
+    arg
+}
+
+#[derive(Debug, PartialEq)]
+struct Failure(u32);
+
+#[test]
+fn then_panics_with_unchanged_payload() {
+    function.mock_safe(|arg| MockResult::Unwind(Box::new(Failure(arg))));
+
+    let payload = panic::catch_unwind(|| function(3)).expect_err("did not panic");
+
+    assert_eq!(Some(&Failure(3)), payload.downcast_ref::<Failure>());
+}
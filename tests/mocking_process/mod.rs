@@ -0,0 +1,6 @@
+use super::*;
+
+mod when_command_run;
+mod when_command_scripted;
+mod when_exit_caught;
+mod when_exit_mocked;
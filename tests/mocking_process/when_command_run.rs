@@ -0,0 +1,26 @@
+use super::*;
+use mocktopus::process::{Command, Output};
+use std::io;
+
+fn current_commit() -> io::Result<String> {
+    let output = Command::new("git").args(["rev-parse", "HEAD"]).output()?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[test]
+fn and_not_mocked_then_runs_real_command() {
+    let output = Command::new("cargo").arg("--version").output().unwrap();
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("cargo"));
+}
+
+#[test]
+fn and_output_mocked_then_mock_receives_command() {
+    Command::output.mock_safe(|command| {
+        assert_eq!("git rev-parse HEAD", command.command_line());
+        MockResult::Return(Ok(Output::new(0, "mocked\n", "")))
+    });
+
+    assert_eq!("mocked", current_commit().unwrap());
+}
@@ -0,0 +1,77 @@
+use super::*;
+use mocktopus::process::{Child, Command, CommandScript, ExitStatus, Output};
+use std::io;
+
+fn current_commit() -> io::Result<String> {
+    let output = Command::new("git").args(["rev-parse", "HEAD"]).output()?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[test]
+fn then_returns_scripted_output() {
+    let script = CommandScript::new();
+    script.on("git rev-parse HEAD").stdout("abc123\n");
+
+    assert_eq!("abc123", current_commit().unwrap());
+}
+
+#[test]
+fn and_command_not_scripted_then_fails_with_not_found() {
+    let script = CommandScript::new();
+    script.on("git status");
+
+    assert_eq!(io::ErrorKind::NotFound, current_commit().unwrap_err().kind());
+}
+
+#[test]
+fn and_status_called_then_returns_scripted_exit_code() {
+    let script = CommandScript::new();
+    script.on("make test").stderr("failed").exit_code(2);
+
+    let status = Command::new("make").arg("test").status().unwrap();
+
+    assert_eq!(ExitStatus::from_code(2), status);
+    assert!(!status.success());
+}
+
+#[test]
+fn and_spawned_then_child_returns_scripted_output() {
+    let script = CommandScript::new();
+    script.on("make build").stdout("done").exit_code(0);
+
+    let mut child: Child = Command::new("make").arg("build").spawn().unwrap();
+
+    assert!(child.wait().unwrap().success());
+    assert_eq!(Output::new(0, "done", ""), child.wait_with_output().unwrap());
+}
+
+#[test]
+fn and_multiple_scripts_match_then_last_one_is_used() {
+    let script = CommandScript::new();
+    script.on("git rev-parse HEAD").stdout("first");
+    script.on("git rev-parse HEAD").stdout("second");
+
+    assert_eq!("second", current_commit().unwrap());
+}
+
+#[test]
+fn and_commands_run_then_they_are_recorded() {
+    let script = CommandScript::new();
+    script.on("git rev-parse HEAD");
+
+    current_commit().unwrap();
+    let _ = Command::new("git").arg("status").status();
+
+    assert_eq!(vec!["git rev-parse HEAD", "git status"], script.executed());
+}
+
+#[test]
+fn and_dropped_then_real_commands_are_run() {
+    let script = CommandScript::new();
+    script.on("cargo --version").stdout("mocked");
+
+    drop(script);
+
+    let output = Command::new("cargo").arg("--version").output().unwrap();
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("cargo"));
+}
@@ -0,0 +1,36 @@
+use super::*;
+use mocktopus::process::{self, catch_exit};
+use std::panic;
+
+fn run(args: &[&str]) -> usize {
+    if args.is_empty() {
+        process::exit(2);
+    }
+    args.len()
+}
+
+#[test]
+fn and_exit_called_then_returns_exit_code() {
+    assert_eq!(Err(2), catch_exit(|| run(&[])));
+}
+
+#[test]
+fn and_exit_not_called_then_returns_result() {
+    assert_eq!(Ok(1), catch_exit(|| run(&["arg"])));
+}
+
+#[test]
+#[should_panic(expected = "other panic")]
+fn and_closure_panics_then_propagates_panic() {
+    let _ = catch_exit(|| panic!("other panic"));
+}
+
+#[test]
+fn and_returned_then_previous_mock_is_restored() {
+    process::exit.mock_safe(|code| MockResult::Panic(format!("exit {}", code)));
+
+    let _ = catch_exit(|| run(&[]));
+
+    let payload = panic::catch_unwind(|| run(&[])).unwrap_err();
+    assert!(payload.downcast_ref::<String>().unwrap().ends_with("exit 2"));
+}
@@ -0,0 +1,19 @@
+use super::*;
+use mocktopus::process::{self, Exit};
+use std::panic;
+
+fn run(args: &[&str]) -> usize {
+    if args.is_empty() {
+        process::exit(2);
+    }
+    args.len()
+}
+
+#[test]
+fn and_unwinds_then_payload_can_be_caught() {
+    process::exit.mock_safe(|code| MockResult::Unwind(Box::new(Exit(code))));
+
+    let payload = panic::catch_unwind(|| run(&[])).unwrap_err();
+
+    assert_eq!(Some(&Exit(2)), payload.downcast_ref::<Exit>());
+}
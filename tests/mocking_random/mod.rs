@@ -0,0 +1,6 @@
+use super::*;
+
+mod when_seed_env_var_set;
+mod when_seeded_mocked;
+mod when_seeded_rng_used;
+mod when_sequence_mocked;
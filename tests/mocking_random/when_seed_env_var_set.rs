@@ -0,0 +1,12 @@
+use super::*;
+use mocktopus::random;
+
+#[test]
+fn then_test_seed_returns_it() {
+    std::env::set_var(random::SEED_ENV_VAR, " 1234 ");
+
+    let seed = random::test_seed();
+
+    std::env::remove_var(random::SEED_ENV_VAR);
+    assert_eq!(1234, seed);
+}
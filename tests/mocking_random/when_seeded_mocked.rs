@@ -0,0 +1,53 @@
+use super::*;
+use mocktopus::random::{self, MockableRandom, Random, SeededRng};
+use std::panic;
+
+#[mockable]
+fn roll_die() -> u8 {
+    4
+}
+
+fn roll_die_values(seed: u64) -> Vec<u8> {
+    let _seed = roll_die.mock_seeded(seed);
+    (0..10).map(|_| roll_die()).collect()
+}
+
+#[test]
+fn then_returns_values_of_seeded_generator() {
+    let mut rng = SeededRng::new(42);
+    let expected: Vec<f64> = (0..10).map(|_| f64::random(&mut rng)).collect();
+    let _seed = random::random_f64.mock_seeded(42);
+
+    let actual: Vec<f64> = (0..10).map(|_| random::random_f64()).collect();
+
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn and_mocked_with_same_seed_then_returns_same_values() {
+    assert_eq!(roll_die_values(7), roll_die_values(7));
+}
+
+#[test]
+fn and_mocked_with_different_seeds_then_returns_different_values() {
+    assert_ne!(roll_die_values(7), roll_die_values(8));
+}
+
+#[test]
+fn and_guard_dropped_then_mock_is_removed() {
+    let guard = roll_die.mock_seeded(7);
+
+    drop(guard);
+
+    assert_eq!(4, roll_die());
+}
+
+#[test]
+fn and_guard_dropped_during_panic_then_panic_propagates() {
+    let result = panic::catch_unwind(|| {
+        let _seed = roll_die.mock_seeded(7);
+        panic!("assertion failed");
+    });
+
+    assert_eq!("assertion failed", *result.unwrap_err().downcast_ref::<&str>().unwrap());
+}
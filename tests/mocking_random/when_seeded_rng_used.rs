@@ -0,0 +1,24 @@
+use super::*;
+use mocktopus::random::{Random, SeededRng};
+
+#[test]
+fn and_created_with_same_seed_then_generates_same_values() {
+    let mut rng_1 = SeededRng::new(1);
+    let mut rng_2 = SeededRng::new(1);
+
+    for _ in 0..10 {
+        assert_eq!(rng_1.next_u64(), rng_2.next_u64());
+    }
+}
+
+#[test]
+fn and_generating_floats_then_they_are_in_unit_range() {
+    let mut rng = SeededRng::new(1);
+
+    for _ in 0..1000 {
+        let value_64 = f64::random(&mut rng);
+        let value_32 = f32::random(&mut rng);
+        assert!((0.0..1.0).contains(&value_64));
+        assert!((0.0..1.0).contains(&value_32));
+    }
+}
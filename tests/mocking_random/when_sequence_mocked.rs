@@ -0,0 +1,47 @@
+use super::*;
+use mocktopus::random::{self, MockableRandom};
+
+#[mockable]
+fn roll_die() -> u8 {
+    4
+}
+
+#[mockable]
+fn pick<'a>(items: &'a [&'a str]) -> usize {
+    items.len() - 1
+}
+
+#[test]
+fn then_returns_values_in_order() {
+    roll_die.mock_sequence(vec![6, 6, 1]);
+
+    assert_eq!(6, roll_die());
+    assert_eq!(6, roll_die());
+    assert_eq!(1, roll_die());
+}
+
+#[test]
+fn and_mocked_function_has_arguments_then_they_are_ignored() {
+    pick.mock_sequence(0..);
+
+    assert_eq!(0, pick(&["a", "b"]));
+    assert_eq!(1, pick(&["a", "b"]));
+}
+
+#[test]
+#[should_panic(expected = "panicked on call 3: sequence of 2 random values is exhausted")]
+fn and_values_exhausted_then_panics() {
+    roll_die.mock_sequence(vec![1, 2]);
+
+    for _ in 0..3 {
+        roll_die();
+    }
+}
+
+#[test]
+fn and_wrapper_mocked_then_returns_values_in_order() {
+    random::random_u64.mock_sequence(vec![u64::MAX, 0]);
+
+    assert_eq!(u64::MAX, random::random_u64());
+    assert_eq!(0, random::random_u64());
+}
@@ -0,0 +1,6 @@
+use super::*;
+
+mod when_fn_returns_mut_ref;
+mod when_fn_returns_ref;
+mod when_method_returns_mut_ref;
+mod when_method_returns_ref;
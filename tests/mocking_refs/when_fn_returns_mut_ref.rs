@@ -0,0 +1,38 @@
+use super::*;
+
+#[mockable]
+fn function(values: &mut Vec<u32>) -> &mut u32 {
+    &mut values[0]
+}
+
+#[test]
+fn and_not_mocked_then_runs_normally() {
+    let mut values = vec![1, 2];
+
+    *function(&mut values) += 10;
+
+    assert_eq!(vec![11, 2], values);
+}
+
+#[test]
+fn and_mocked_then_returns_mut_ref_borrowed_from_arg() {
+    function.mock_mut(|values: &mut Vec<u32>| MockResult::Return(&mut values[1]));
+    let mut values = vec![1, 2];
+
+    *function(&mut values) += 10;
+
+    assert_eq!(vec![1, 12], values);
+}
+
+#[test]
+fn and_continue_mocked_then_runs_with_modified_arg() {
+    function.mock_mut(|values: &mut Vec<u32>| {
+        values.insert(0, 0);
+        MockResult::Continue((values,))
+    });
+    let mut values = vec![1, 2];
+
+    *function(&mut values) += 10;
+
+    assert_eq!(vec![10, 1, 2], values);
+}
@@ -0,0 +1,45 @@
+use super::*;
+
+#[mockable]
+fn function(text: &str) -> &str {
+    text
+}
+
+#[test]
+fn and_not_mocked_then_runs_normally() {
+    assert_eq!("abc", function("abc"));
+}
+
+#[test]
+fn and_mocked_then_returns_ref_borrowed_from_arg() {
+    function.mock_ref(|text: &str| MockResult::Return(&text[1..]));
+
+    let text = "abc".to_string();
+    assert_eq!("bc", function(&text));
+    drop(text);
+    let other_text = "def".to_string();
+    assert_eq!("ef", function(&other_text));
+}
+
+#[test]
+fn and_continue_mocked_then_runs_with_modified_arg() {
+    function.mock_ref(|text: &str| MockResult::Continue((&text[2..],)));
+
+    assert_eq!("c", function("abc"));
+}
+
+#[test]
+fn and_mocked_with_mock_safe_then_mock_ref_replaces_it() {
+    function.mock_safe(|_| MockResult::Return("mock safe"));
+    function.mock_ref(|text: &str| MockResult::Return(&text[1..]));
+
+    assert_eq!("bc", function("abc"));
+}
+
+#[test]
+fn and_mocked_then_clear_mocks_removes_mock() {
+    function.mock_ref(|text: &str| MockResult::Return(&text[1..]));
+    clear_mocks();
+
+    assert_eq!("abc", function("abc"));
+}
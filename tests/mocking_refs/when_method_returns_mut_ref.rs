@@ -0,0 +1,24 @@
+use super::*;
+
+struct Struct {
+    first: String,
+    second: String,
+}
+
+#[mockable]
+impl Struct {
+    fn get_mut(&mut self) -> &mut String {
+        &mut self.first
+    }
+}
+
+#[test]
+fn and_mocked_then_returns_mut_ref_borrowed_from_receiver() {
+    Struct::get_mut.mock_mut(|s: &mut Struct| MockResult::Return(&mut s.second));
+    let mut s = Struct { first: "first".to_string(), second: "second".to_string() };
+
+    s.get_mut().push_str(" mocked");
+
+    assert_eq!("first", s.first);
+    assert_eq!("second mocked", s.second);
+}
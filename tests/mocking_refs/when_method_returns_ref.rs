@@ -0,0 +1,21 @@
+use super::*;
+
+struct Struct {
+    first: String,
+    second: String,
+}
+
+#[mockable]
+impl Struct {
+    fn get(&self) -> &String {
+        &self.first
+    }
+}
+
+#[test]
+fn and_mocked_then_returns_ref_borrowed_from_receiver() {
+    Struct::get.mock_ref(|s: &Struct| MockResult::Return(&s.second));
+    let s = Struct { first: "first".to_string(), second: "second".to_string() };
+
+    assert_eq!("second", s.get());
+}
@@ -0,0 +1,6 @@
+use super::*;
+
+mod when_function_panics;
+mod when_method_mocked;
+mod when_rule_continues;
+mod when_rule_matches;
@@ -0,0 +1,35 @@
+use super::*;
+
+#[mockable]
+fn get(key: u32) -> Option<&'static str> {
+    match key {
+        0 => Some("zero"),
+        _ => None,
+    }
+}
+
+#[mockable]
+fn put(key: u32, value: &'static str) -> bool {
+    key != 0 && !value.is_empty()
+}
+
+#[test]
+#[should_panic(expected = "mock of mocking::mocking_rules::when_function_panics::put panicked on call 1: read-only store, key 3")]
+fn and_rule_panics_then_message_is_formatted() {
+    mock! {
+        put(key, _) => panic("read-only store, key {}", key),
+    }
+
+    put(3, "value");
+}
+
+#[test]
+#[should_panic(expected = "no mock! rule matches the call, rules: get(1), get(2)")]
+fn and_no_rule_matches_then_rules_are_listed() {
+    mock! {
+        get(1) => Some("one"),
+        get(2) => None,
+    }
+
+    get(3);
+}
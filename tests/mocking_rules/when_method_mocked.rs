@@ -0,0 +1,21 @@
+use super::*;
+
+struct Store;
+
+#[mockable]
+impl Store {
+    fn get(&self, key: u32) -> u32 {
+        key
+    }
+}
+
+#[test]
+fn then_receiver_is_first_argument() {
+    mock! {
+        Store::get(_, 1) => 10,
+        Store::get(_, key) => key * 2,
+    }
+
+    assert_eq!(10, Store.get(1));
+    assert_eq!(4, Store.get(2));
+}
@@ -0,0 +1,31 @@
+use super::*;
+
+#[mockable]
+fn put(key: u32, value: &'static str) -> bool {
+    key != 0 && !value.is_empty()
+}
+
+#[mockable]
+fn greet(name: String) -> String {
+    format!("Hello, {}!", name)
+}
+
+#[test]
+fn and_pattern_binds_not_copy_argument_then_function_runs_with_it() {
+    mock! {
+        greet(name) if name.is_empty() => continue,
+        greet(name) => name,
+    }
+
+    assert_eq!("Hello, !", greet(String::new()));
+    assert_eq!("World", greet("World".to_string()));
+}
+
+#[test]
+fn and_given_arguments_then_function_runs_with_them() {
+    mock! {
+        put(0, value) => continue(1, value),
+    }
+
+    assert!(put(0, "value"));
+}
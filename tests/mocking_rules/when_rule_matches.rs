@@ -0,0 +1,81 @@
+use super::*;
+
+#[mockable]
+fn get(key: u32) -> Option<&'static str> {
+    match key {
+        0 => Some("zero"),
+        _ => None,
+    }
+}
+
+#[mockable]
+fn put(key: u32, value: &'static str) -> bool {
+    key != 0 && !value.is_empty()
+}
+
+#[mockable]
+fn constant() -> u32 {
+    1
+}
+
+#[test]
+fn and_argument_matches_literal_then_returns_value() {
+    mock! {
+        get(1) => Some("one"),
+        get(_) => continue,
+    }
+
+    assert_eq!(Some("one"), get(1));
+    assert_eq!(Some("zero"), get(0));
+    assert_eq!(None, get(2));
+}
+
+#[test]
+fn and_pattern_binds_argument_then_it_can_be_used_in_guard_and_value() {
+    mock! {
+        put(key, _) if key > 10 => key % 2 == 0,
+        put(..) => continue,
+    }
+
+    assert!(put(12, ""));
+    assert!(!put(13, "value"));
+    assert!(put(1, "value"));
+}
+
+#[test]
+fn and_rule_uses_captured_value_then_it_is_moved_into_mock() {
+    let value = 5;
+
+    mock! {
+        constant() => value,
+    }
+
+    assert_eq!(5, constant());
+}
+
+#[test]
+fn and_many_functions_then_all_are_mocked() {
+    mock! {
+        get(_) => Some("mocked"),
+        constant() => 2,
+        put(..) => false,
+    }
+
+    assert_eq!(Some("mocked"), get(0));
+    assert_eq!(2, constant());
+    assert!(!put(1, "value"));
+}
+
+#[test]
+fn and_mocked_again_then_previous_rules_are_replaced() {
+    mock! {
+        get(_) => Some("first"),
+    }
+    mock! {
+        get(1) => Some("second"),
+        get(_) => continue,
+    }
+
+    assert_eq!(Some("second"), get(1));
+    assert_eq!(Some("zero"), get(0));
+}
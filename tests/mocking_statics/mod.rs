@@ -0,0 +1,7 @@
+use super::*;
+
+mod when_lazy_static;
+mod when_static;
+mod when_static_has_cfg;
+mod when_static_uses_given_crate_path;
+mod when_thread_local;
@@ -0,0 +1,30 @@
+use super::*;
+
+#[mockable_static]
+lazy_static! {
+    static ref VALUE: String = "not mocked".to_string();
+    pub static ref LENGTH: usize = VALUE.len();
+}
+
+#[test]
+fn and_not_mocked_then_returns_value() {
+    assert_eq!("not mocked", *VALUE);
+    assert_eq!(10, *LENGTH);
+}
+
+#[test]
+fn and_mocked_then_returns_mock() {
+    let _guard = VALUE.mock_static("mocked".to_string());
+
+    assert_eq!("mocked", *VALUE);
+    assert_eq!(6, VALUE.len());
+}
+
+#[test]
+fn and_guard_dropped_then_returns_value() {
+    let guard = VALUE.mock_static("mocked".to_string());
+
+    drop(guard);
+
+    assert_eq!("not mocked", *VALUE);
+}
@@ -0,0 +1,55 @@
+use super::*;
+use std::thread;
+
+#[mockable_static]
+pub static VALUE: &str = "not mocked";
+
+#[mockable_static]
+static NUMBERS: [u32; 3] = [1, 2, 3];
+
+fn sum() -> u32 {
+    NUMBERS.iter().sum()
+}
+
+#[test]
+fn and_not_mocked_then_returns_value() {
+    assert_eq!("not mocked", *VALUE);
+    assert_eq!(6, sum());
+}
+
+#[test]
+fn and_mocked_then_returns_mock() {
+    let _guard = VALUE.mock_static("mocked");
+    let _numbers_guard = NUMBERS.mock_static([4, 5, 6]);
+
+    assert_eq!("mocked", *VALUE);
+    assert_eq!(15, sum());
+}
+
+#[test]
+fn and_guard_dropped_then_returns_value() {
+    let guard = VALUE.mock_static("mocked");
+
+    drop(guard);
+
+    assert_eq!("not mocked", *VALUE);
+}
+
+#[test]
+fn and_mocked_then_other_threads_return_value() {
+    let _guard = VALUE.mock_static("mocked");
+
+    let other_thread_value = thread::spawn(|| *VALUE).join().unwrap();
+
+    assert_eq!("not mocked", other_thread_value);
+}
+
+#[test]
+fn and_mocked_then_returned_reference_outlives_guard() {
+    let guard = VALUE.mock_static("mocked");
+    let value: &'static &str = &VALUE;
+
+    drop(guard);
+
+    assert_eq!("mocked", *value);
+}
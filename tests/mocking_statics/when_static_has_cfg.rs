@@ -0,0 +1,21 @@
+use super::*;
+
+#[mockable_static]
+#[cfg(target_os = "none")]
+static VALUE: &str = "configured out";
+
+#[mockable_static]
+#[cfg(not(target_os = "none"))]
+static VALUE: &str = "not mocked";
+
+#[test]
+fn and_not_mocked_then_returns_configured_value() {
+    assert_eq!("not mocked", *VALUE);
+}
+
+#[test]
+fn and_mocked_then_returns_mock() {
+    let _guard = VALUE.mock_static("mocked");
+
+    assert_eq!("mocked", *VALUE);
+}
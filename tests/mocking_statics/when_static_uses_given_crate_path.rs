@@ -0,0 +1,15 @@
+use super::*;
+
+mod reexported {
+    pub(super) use mocktopus as mocktopus_reexport;
+}
+
+#[mockable_static(mocktopus = "self::reexported::mocktopus_reexport")]
+static VALUE: &str = "not mocked";
+
+#[test]
+fn and_mocked_then_returns_mock() {
+    let _guard = VALUE.mock_static("mocked");
+
+    assert_eq!("mocked", *VALUE);
+}
@@ -0,0 +1,40 @@
+use super::*;
+use std::cell::Cell;
+
+#[mockable_static]
+thread_local! {
+    static COUNTER: Cell<u32> = Cell::new(0);
+}
+
+fn increment() -> u32 {
+    COUNTER.with(|counter| {
+        counter.set(counter.get() + 1);
+        counter.get()
+    })
+}
+
+#[test]
+fn and_not_mocked_then_returns_value() {
+    assert_eq!(1, increment());
+    assert_eq!(2, increment());
+}
+
+#[test]
+fn and_mocked_then_returns_mock() {
+    let _guard = COUNTER.mock_static(Cell::new(10));
+
+    assert_eq!(11, increment());
+    assert_eq!(12, increment());
+}
+
+#[test]
+fn and_guard_dropped_then_returns_value() {
+    assert_eq!(1, increment());
+    let guard = COUNTER.mock_static(Cell::new(10));
+    assert_eq!(11, increment());
+
+    drop(guard);
+
+    assert_eq!(2, increment());
+    assert_eq!(Ok(3), COUNTER.try_with(|counter| counter.get() + 1));
+}
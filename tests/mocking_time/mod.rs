@@ -0,0 +1,4 @@
+use super::*;
+
+mod when_fake_clock_used;
+mod when_system_time_now_called;
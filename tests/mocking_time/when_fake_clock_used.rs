@@ -0,0 +1,54 @@
+use super::*;
+use mocktopus::time::{self, FakeClock};
+use std::time::{Duration, UNIX_EPOCH};
+
+#[test]
+fn and_created_then_time_stops() {
+    let _clock = FakeClock::new();
+
+    let instant = time::instant_now();
+    let system_time = time::system_time_now();
+
+    assert_eq!(instant, time::instant_now());
+    assert_eq!(system_time, time::system_time_now());
+}
+
+#[test]
+fn and_created_at_system_time_then_reports_it() {
+    let _clock = FakeClock::starting_at(UNIX_EPOCH);
+
+    assert_eq!(UNIX_EPOCH, time::system_time_now());
+}
+
+#[test]
+fn and_advanced_then_time_moves_forward() {
+    let clock = FakeClock::starting_at(UNIX_EPOCH);
+    let instant = time::instant_now();
+
+    clock.advance(Duration::from_secs(5));
+
+    assert_eq!(Duration::from_secs(5), time::instant_now() - instant);
+    assert_eq!(UNIX_EPOCH + Duration::from_secs(5), time::system_time_now());
+    assert_eq!(Duration::from_secs(5), clock.elapsed());
+}
+
+#[test]
+fn and_sleeping_then_returns_immediately_and_time_moves_forward() {
+    let clock = FakeClock::new();
+    let real_instant = std::time::Instant::now();
+
+    time::sleep(Duration::from_secs(3600));
+
+    assert!(real_instant.elapsed() < Duration::from_secs(60));
+    assert_eq!(Duration::from_secs(3600), clock.elapsed());
+}
+
+#[test]
+fn and_dropped_then_previous_mocks_are_restored() {
+    time::system_time_now.mock_safe(|| MockResult::Return(UNIX_EPOCH + Duration::from_secs(1)));
+    let clock = FakeClock::starting_at(UNIX_EPOCH);
+
+    drop(clock);
+
+    assert_eq!(UNIX_EPOCH + Duration::from_secs(1), time::system_time_now());
+}
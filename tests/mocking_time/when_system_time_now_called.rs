@@ -0,0 +1,19 @@
+use super::*;
+use mocktopus::time;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[test]
+fn and_not_mocked_then_returns_real_time() {
+    let before = SystemTime::now();
+
+    let now = time::system_time_now();
+
+    assert!(before <= now && now <= SystemTime::now());
+}
+
+#[test]
+fn and_mocked_then_returns_mocked_time() {
+    time::system_time_now.mock_safe(|| MockResult::Return(UNIX_EPOCH));
+
+    assert_eq!(UNIX_EPOCH, time::system_time_now());
+}
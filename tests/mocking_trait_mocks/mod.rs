@@ -0,0 +1,5 @@
+use super::*;
+
+mod when_trait_generic;
+mod when_trait_has_supertrait;
+mod when_trait_regular;
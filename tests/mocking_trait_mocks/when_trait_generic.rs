@@ -0,0 +1,34 @@
+use super::*;
+
+#[mockable_trait]
+trait Store<'a, T: Display + ?Sized> {
+    fn get(&self, key: &'a T) -> String;
+    fn get_generic<U: Display>(&self, key: &'a T, generic: U) -> String where Self: Sized;
+}
+
+fn call_through_dyn<'a>(store: &dyn Store<'a, str>, key: &'a str) -> String {
+    store.get(key)
+}
+
+#[test]
+fn and_mocked_then_returns_mocking_result_for_mocked_type_only() {
+    MockStore::<str>::get.mock_safe(|_, key| MockResult::Return(format!("mocked str {}", key)));
+
+    assert_eq!("mocked str key", call_through_dyn(&MockStore::default(), "key"));
+}
+
+#[test]
+#[should_panic(expected = "MockStore::get was called, but it's not mocked")]
+fn and_mocked_for_other_type_then_panics() {
+    MockStore::<u32>::get.mock_safe(|_, key| MockResult::Return(format!("mocked u32 {}", key)));
+
+    call_through_dyn(&MockStore::default(), "key");
+}
+
+#[test]
+fn and_generic_method_mocked_then_returns_mocking_result_for_mocked_type_only() {
+    MockStore::<str>::get_generic::<u8>.mock_safe(|_, key, generic|
+        MockResult::Return(format!("mocked {} {}", key, generic)));
+
+    assert_eq!("mocked key 1", MockStore::<str>::new().get_generic("key", 1u8));
+}
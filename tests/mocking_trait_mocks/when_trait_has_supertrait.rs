@@ -0,0 +1,24 @@
+use super::*;
+
+use std::fmt::{Debug, Error, Formatter};
+
+#[mockable_trait]
+trait Store: Debug {
+    fn get(&self) -> u32;
+}
+
+impl Debug for MockStore {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write!(f, "mock store")
+    }
+}
+
+#[test]
+fn and_mocked_then_returns_mocking_result() {
+    MockStore::get.mock_safe(|_| MockResult::Return(1));
+
+    let store: Box<dyn Store> = Box::new(MockStore);
+
+    assert_eq!(1, store.get());
+    assert_eq!("mock store", format!("{:?}", store));
+}
@@ -0,0 +1,61 @@
+use super::*;
+
+#[mockable_trait]
+pub trait Store {
+    fn static_method(arg: bool) -> String where Self: Sized;
+    fn ref_method(&self, arg: bool) -> String;
+    fn ref_mut_method(&mut self, arg: bool) -> String;
+    fn val_method(self, arg: bool) -> String where Self: Sized;
+
+    fn default_method(&self, arg: bool) -> String {
+        format!("default {}", arg)
+    }
+}
+
+fn call_through_dyn(store: &mut dyn Store) -> String {
+    format!("{} {}", store.ref_method(true), store.ref_mut_method(false))
+}
+
+fn call_through_generic(store: impl Store) -> String {
+    store.val_method(true)
+}
+
+#[test]
+#[should_panic(expected = "MockStore::ref_method was called, but it's not mocked")]
+fn and_not_mocked_then_panics() {
+    MockStore::new().ref_method(true);
+}
+
+#[test]
+fn and_static_method_mocked_then_returns_mocking_result() {
+    MockStore::static_method.mock_safe(|a| MockResult::Return(format!("mocked {}", a)));
+
+    assert_eq!("mocked true", MockStore::static_method(true));
+}
+
+#[test]
+fn and_mocked_then_returns_mocking_result_through_trait_object() {
+    MockStore::ref_method.mock_safe(|_, a| MockResult::Return(format!("mocked ref {}", a)));
+    MockStore::ref_mut_method.mock_safe(|_, a| MockResult::Return(format!("mocked ref mut {}", a)));
+
+    assert_eq!("mocked ref true mocked ref mut false", call_through_dyn(&mut MockStore));
+}
+
+#[test]
+fn and_mocked_then_returns_mocking_result_through_generic() {
+    MockStore::val_method.mock_safe(|_, a| MockResult::Return(format!("mocked val {}", a)));
+
+    assert_eq!("mocked val true", call_through_generic(MockStore::new()));
+}
+
+#[test]
+fn and_default_method_not_mocked_then_runs_default() {
+    assert_eq!("default true", MockStore.default_method(true));
+}
+
+#[test]
+fn and_default_method_mocked_then_returns_mocking_result() {
+    MockStore::default_method.mock_safe(|_, a| MockResult::Continue((&MockStore, !a)));
+
+    assert_eq!("default false", MockStore.default_method(true));
+}
@@ -63,3 +63,197 @@ mod mocking_impls_of_traits_generic_over_generic_refs {
         assert_eq!("mocked", <Struct as Trait<&u32>>::method());
     }
 }
+
+mod mocking_trait_methods_for_all_implementors {
+    use super::*;
+
+    trait Plugin {
+        fn create() -> Self where Self: Sized;
+        fn name(&self, prefix: &str) -> String;
+    }
+
+    struct FirstPlugin(u32);
+
+    #[mockable]
+    impl Plugin for FirstPlugin {
+        fn create() -> Self {
+            FirstPlugin(1)
+        }
+
+        fn name(&self, prefix: &str) -> String {
+            format!("{} first", prefix)
+        }
+    }
+
+    struct SecondPlugin;
+
+    #[mockable]
+    impl Plugin for SecondPlugin {
+        fn create() -> Self {
+            SecondPlugin
+        }
+
+        fn name(&self, prefix: &str) -> String {
+            format!("{} second", prefix)
+        }
+    }
+
+    struct BorrowedPlugin<'a>(&'a str);
+
+    #[mockable]
+    impl<'a> Plugin for BorrowedPlugin<'a> {
+        fn create() -> Self {
+            BorrowedPlugin("created")
+        }
+
+        fn name(&self, prefix: &str) -> String {
+            format!("{} {}", prefix, self.0)
+        }
+    }
+
+    fn plugins() -> Vec<Box<dyn Plugin>> {
+        vec![Box::new(FirstPlugin(2)), Box::new(SecondPlugin)]
+    }
+
+    fn names(prefix: &str) -> Vec<String> {
+        plugins().iter()
+            .map(|plugin| plugin.name(prefix))
+            .collect()
+    }
+
+    #[test]
+    fn when_not_mocked_then_runs_normally() {
+        assert_eq!(vec!["plugin first", "plugin second"], names("plugin"));
+    }
+
+    #[test]
+    fn when_return_mocked_then_returns_mocking_result_for_all_implementors() {
+        mock_trait_method!(Plugin::name, |_: TraitImplementor, prefix: &str|
+            MockResult::Return(format!("{} mocked", prefix)));
+
+        assert_eq!(vec!["plugin mocked", "plugin mocked"], names("plugin"));
+    }
+
+    #[test]
+    fn when_continue_mocked_then_runs_with_modified_args_for_all_implementors() {
+        mock_trait_method!(Plugin::name, |_: TraitImplementor, _: &str|
+            MockResult::Continue::<_, String>(("mocked",)));
+
+        assert_eq!(vec!["mocked first", "mocked second"], names("plugin"));
+    }
+
+    #[test]
+    fn when_mocked_then_mock_receives_implementor() {
+        mock_trait_method!(Plugin::name, |this: TraitImplementor, prefix: &str| {
+            let value = this.downcast_ref::<FirstPlugin>().map(|plugin| plugin.0);
+            MockResult::Return(format!("{} {} {:?}", prefix, this.is::<SecondPlugin>(), value))
+        });
+
+        assert_eq!(vec!["plugin false Some(2)", "plugin true None"], names("plugin"));
+    }
+
+    #[test]
+    fn when_implementor_may_be_not_static_then_mock_receives_implementor_without_receiver() {
+        mock_trait_method!(Plugin::name, |this: TraitImplementor, prefix: &str| {
+            let value = this.downcast_ref::<BorrowedPlugin>().map(|plugin| plugin.0);
+            MockResult::Return(format!("{} {} {:?}", prefix, this.is::<BorrowedPlugin>(), value))
+        });
+        let name = "borrowed".to_string();
+
+        assert_eq!("plugin true None", BorrowedPlugin(&name).name("plugin"));
+    }
+
+    #[test]
+    fn when_mocked_with_path_then_returns_mocking_result_for_all_implementors() {
+        mock_trait_method!(self::Plugin::name, |_: TraitImplementor, prefix: &str|
+            MockResult::Return(format!("{} mocked", prefix)));
+
+        assert_eq!(vec!["plugin mocked", "plugin mocked"], names("plugin"));
+    }
+
+    #[test]
+    fn when_implementor_mocked_then_its_mock_takes_precedence() {
+        mock_trait_method!(Plugin::name, |_: TraitImplementor, prefix: &str|
+            MockResult::Return(format!("{} mocked", prefix)));
+        SecondPlugin::name.mock_safe(|_, prefix| MockResult::Return(format!("{} mocked second", prefix)));
+
+        assert_eq!(vec!["plugin mocked", "plugin mocked second"], names("plugin"));
+    }
+
+    #[test]
+    fn when_static_method_mocked_then_mock_receives_implementor_type() {
+        mock_trait_method!(Plugin::create, |this: TraitImplementor| {
+            assert!(this.type_name().ends_with("FirstPlugin"));
+            assert!(this.downcast_ref::<FirstPlugin>().is_none());
+            MockResult::Return(FirstPlugin(3))
+        });
+
+        assert_eq!(3, FirstPlugin::create().0);
+    }
+
+    #[test]
+    fn when_mocked_with_different_signature_then_runs_normally() {
+        mock_trait_method!(Plugin::name, |_: TraitImplementor, _: u32| MockResult::Return("mocked".to_string()));
+
+        assert_eq!(vec!["plugin first", "plugin second"], names("plugin"));
+    }
+
+    mod when_trait_method_has_many_args {
+        use super::*;
+
+        trait Sum {
+            #[allow(clippy::too_many_arguments)]
+            fn sum(&self, a1: u8, a2: u8, a3: u8, a4: u8, a5: u8, a6: u8, a7: u8, a8: u8, a9: u8, a10: u8, a11: u8,
+                   a12: u8, a13: u8) -> u8;
+        }
+
+        #[mockable]
+        impl Sum for FirstPlugin {
+            fn sum(&self, a1: u8, a2: u8, a3: u8, a4: u8, a5: u8, a6: u8, a7: u8, a8: u8, a9: u8, a10: u8, a11: u8,
+                   a12: u8, a13: u8) -> u8 {
+                a1 + a2 + a3 + a4 + a5 + a6 + a7 + a8 + a9 + a10 + a11 + a12 + a13
+            }
+        }
+
+        #[test]
+        fn then_runs_normally() {
+            assert_eq!(13, FirstPlugin(1).sum(1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1));
+        }
+
+        #[test]
+        fn then_implementor_can_be_mocked() {
+            <FirstPlugin as Sum>::sum.mock_safe(|_, a1, _, _, _, _, _, _, _, _, _, _, _, a13|
+                MockResult::Return(a1 + a13));
+
+            assert_eq!(3, FirstPlugin(1).sum(1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 2));
+        }
+    }
+
+    mod when_trait_default_method_mocked {
+        use super::*;
+
+        #[mockable]
+        trait Describe {
+            fn describe(&self) -> String {
+                "not mocked".to_string()
+            }
+        }
+
+        impl Describe for FirstPlugin {
+
+        }
+
+        impl Describe for SecondPlugin {
+
+        }
+
+        #[test]
+        fn then_returns_mocking_result_for_all_implementors() {
+            mock_trait_method!(Describe::describe, |this: TraitImplementor|
+                MockResult::Return(format!("mocked {}", this.is::<FirstPlugin>())));
+
+            assert_eq!("mocked true", FirstPlugin(1).describe());
+            assert_eq!("mocked false", SecondPlugin.describe());
+        }
+    }
+}
@@ -0,0 +1,4 @@
+use super::*;
+
+mod when_function_annotated;
+mod when_test_annotated;
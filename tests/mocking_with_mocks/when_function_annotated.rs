@@ -0,0 +1,40 @@
+use super::*;
+use std::panic;
+
+#[mockable]
+fn world() -> String {
+    "world".to_string()
+}
+
+#[with_mocks(world => "mocking".to_string())]
+fn run_with_value_mock() -> String {
+    world()
+}
+
+#[with_mocks(world => "mocking".to_string())]
+fn run_with_panic() {
+    panic!("test failed");
+}
+
+#[test]
+fn and_returns_then_mocks_are_removed() {
+    assert_eq!("mocking", run_with_value_mock());
+
+    assert_eq!("world", world());
+}
+
+#[test]
+fn and_panics_then_mocks_are_removed() {
+    assert!(panic::catch_unwind(run_with_panic).is_err());
+
+    assert_eq!("world", world());
+}
+
+#[test]
+fn and_mock_set_before_then_it_is_restored() {
+    world.mock_safe(|| MockResult::Return("before".to_string()));
+
+    assert_eq!("mocking", run_with_value_mock());
+
+    assert_eq!("before", world());
+}
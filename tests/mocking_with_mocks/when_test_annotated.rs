@@ -0,0 +1,65 @@
+use super::*;
+
+#[mockable]
+fn world() -> String {
+    "world".to_string()
+}
+
+#[mockable]
+fn greet(name: &str) -> String {
+    format!("hello {}", name)
+}
+
+#[mockable]
+fn add(a: u32, b: u32) -> u32 {
+    a + b
+}
+
+#[test]
+#[with_mocks(world => "mocking".to_string())]
+fn and_value_given_then_returns_it_on_every_call() {
+    assert_eq!("mocking", world());
+    assert_eq!("mocking", world());
+}
+
+#[test]
+#[with_mocks(add => 0)]
+fn and_value_given_for_function_with_arguments_then_arguments_are_ignored() {
+    assert_eq!(0, add(1, 2));
+}
+
+#[test]
+#[with_mocks(greet => |name| MockResult::Continue((&name[1..],)))]
+fn and_closure_given_then_it_is_mock() {
+    assert_eq!("hello orld", greet("world"));
+}
+
+#[with_mocks(
+    world => "mocking".to_string(),
+    add => |a, b| MockResult::Return(a * b),
+)]
+#[test]
+fn and_many_mocks_given_then_all_are_set() {
+    assert_eq!("mocking", world());
+    assert_eq!(6, add(2, 3));
+}
+
+#[test]
+#[with_mocks(add => 5)]
+fn and_test_returns_result_then_question_mark_works() -> Result<(), String> {
+    let value: u32 = "5".parse().map_err(|_| "not a number".to_string())?;
+
+    assert_eq!(value, add(1, 1));
+    Ok(())
+}
+
+#[mocktopus::mocktopus_test]
+#[with_mocks(add => |_, _| MockResult::Return(1))]
+fn and_used_with_test_attribute_then_mocks_are_set_after_clearing() {
+    assert_eq!(1, add(2, 3));
+}
+
+#[mocktopus::mocktopus_test(strict)]
+#[with_mocks(add => |_, _| MockResult::Return(1))]
+#[should_panic(expected = "mock of mocking::mocking_with_mocks::when_test_annotated::add was never called")]
+fn and_used_with_strict_test_attribute_and_mock_not_called_then_fails() {}
@@ -0,0 +1,4 @@
+use super::*;
+
+mod when_wrapped_with_attribute;
+mod when_wrapped_with_mock_extern;
@@ -0,0 +1,55 @@
+use super::*;
+use std::io;
+
+mod external {
+    use std::fmt::Display;
+
+    pub fn function(arg: &str) -> String {
+        format!("not mocked {}", arg)
+    }
+
+    pub fn generic<T: Display>(arg: T) -> String {
+        format!("not mocked {}", arg)
+    }
+}
+
+#[mockable_wrap(path = "external::function")]
+fn function(arg: &str) -> String;
+
+#[mockable_wrap(path = "external::generic")]
+/// Less generic than wrapped function
+fn generic<T: Display>(arg: T) -> String;
+
+#[mockable_wrap(path = "std::fs::read_to_string")]
+fn read_to_string(path: &str) -> io::Result<String>;
+
+#[test]
+fn and_not_mocked_then_runs_wrapped_function() {
+    assert_eq!("not mocked arg", function("arg"));
+    assert_eq!("not mocked 1", generic(1));
+    assert!(read_to_string("/mocktopus/not/existing").is_err());
+}
+
+#[test]
+fn and_mocked_then_returns_mock() {
+    function.mock_safe(|arg| MockResult::Return(format!("mocked {}", arg)));
+    read_to_string.mock_safe(|_| MockResult::Return(Ok("mocked".to_string())));
+
+    assert_eq!("mocked arg", function("arg"));
+    assert_eq!("mocked", read_to_string("/mocktopus/not/existing").unwrap());
+}
+
+#[test]
+fn and_mocked_continue_then_runs_wrapped_function_with_modified_args() {
+    function.mock_safe(|_| MockResult::Continue(("modified",)));
+
+    assert_eq!("not mocked modified", function("arg"));
+}
+
+#[test]
+fn and_generic_mocked_then_mocks_only_mocked_variant() {
+    generic::<u32>.mock_safe(|arg| MockResult::Return(format!("mocked {}", arg)));
+
+    assert_eq!("mocked 1", generic(1u32));
+    assert_eq!("not mocked 1", generic(1i32));
+}
@@ -0,0 +1,55 @@
+use super::*;
+use std::io;
+
+mod external {
+    use std::fmt::Display;
+
+    pub fn function(arg: &str) -> String {
+        format!("not mocked {}", arg)
+    }
+
+    pub fn generic<T: Display>(arg: T) -> String {
+        format!("not mocked {}", arg)
+    }
+
+    pub fn not_wrapped() -> &'static str {
+        "not wrapped"
+    }
+}
+
+mocktopus::mock_extern! {
+    mod wrapped = super::external {
+        fn function(arg: &str) -> String;
+        fn generic<T: Display>(arg: T) -> String;
+    }
+
+    pub mod fs = std::fs {
+        fn read_to_string(path: &str) -> io::Result<String>;
+    }
+}
+
+#[test]
+fn and_not_mocked_then_runs_wrapped_functions() {
+    assert_eq!("not mocked arg", wrapped::function("arg"));
+    assert_eq!("not mocked 1", wrapped::generic(1));
+    assert!(fs::read_to_string("/mocktopus/not/existing").is_err());
+}
+
+#[test]
+fn and_mocked_then_returns_mock() {
+    wrapped::function.mock_safe(|arg| MockResult::Return(format!("mocked {}", arg)));
+    wrapped::generic::<u32>.mock_safe(|arg| MockResult::Return(format!("mocked {}", arg)));
+    fs::read_to_string.mock_safe(|_| MockResult::Return(Ok("mocked".to_string())));
+
+    assert_eq!("mocked arg", wrapped::function("arg"));
+    assert_eq!("mocked 1", wrapped::generic(1u32));
+    assert_eq!("not mocked 1", wrapped::generic(1i32));
+    assert_eq!("mocked", fs::read_to_string("/mocktopus/not/existing").unwrap());
+}
+
+#[test]
+fn then_not_wrapped_items_are_reexported() {
+    assert_eq!("not wrapped", wrapped::not_wrapped());
+    assert!(fs::metadata("/mocktopus/not/existing").is_err());
+    let _: Option<fs::File> = None;
+}